@@ -0,0 +1,375 @@
+//! Sounds and desktop notifications fired when a phase ends.
+//!
+//! [`Alerts`] decides *what* to play (bundled or custom sound, volume, silent
+//! mode); an [`AlertBackend`] decides *how*. The system backend shells out to
+//! the platform's audio player and notifier, tests swap in a recorder.
+
+use crate::model::{AlertSound, Settings};
+use crate::timer::Phase;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const CHIME: &[u8] = include_bytes!("../assets/sounds/chime.wav");
+const BELL: &[u8] = include_bytes!("../assets/sounds/bell.wav");
+const WOOD: &[u8] = include_bytes!("../assets/sounds/wood.wav");
+
+/// Toasts need an app id Windows knows about; PowerShell's own is always there.
+const POWERSHELL_APP_ID: &str = r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// Numbers the scratch copies so overlapping alerts never share a file.
+static PLAYS: AtomicUsize = AtomicUsize::new(0);
+
+pub trait AlertBackend: fmt::Debug + Send + Sync {
+    /// Plays a sound file held in memory, without blocking.
+    fn play(&self, sound: &[u8]) -> io::Result<()>;
+    fn notify(&self, title: &str, body: &str) -> io::Result<()>;
+}
+
+/// Plays through `afplay`, `paplay`/`aplay` or PowerShell and notifies
+/// through `osascript`, a PowerShell toast or `notify-send`, whichever the
+/// platform has.
+#[derive(Debug)]
+pub struct SystemBackend;
+
+impl AlertBackend for SystemBackend {
+    fn play(&self, sound: &[u8]) -> io::Result<()> {
+        // Players want a path, so hand them a scratch copy, removed once played
+        let scratch = std::env::temp_dir().join(format!(
+            "pomimi-alert-{}-{}.wav",
+            std::process::id(),
+            PLAYS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&scratch, sound)?;
+        let path = scratch.to_string_lossy().into_owned();
+
+        let candidates: Vec<(&str, Vec<String>)> = if cfg!(target_os = "macos") {
+            vec![("afplay", vec![path])]
+        } else if cfg!(windows) {
+            let script = format!("(New-Object Media.SoundPlayer {}).PlaySync()", powershell_string(&path));
+            vec![("powershell", vec!["-NoProfile".to_string(), "-Command".to_string(), script])]
+        } else {
+            vec![("paplay", vec![path.clone()]), ("aplay", vec!["-q".to_string(), path])]
+        };
+        spawn_first(&candidates, Some(scratch))
+    }
+
+    fn notify(&self, title: &str, body: &str) -> io::Result<()> {
+        if cfg!(target_os = "macos") {
+            let script = format!(
+                "display notification \"{}\" with title \"{}\"",
+                body.replace('"', "\\\""),
+                title.replace('"', "\\\"")
+            );
+            spawn_first(&[("osascript", vec!["-e".to_string(), script])], None)
+        } else if cfg!(windows) {
+            let script = format!(
+                "$null = [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime]
+                 $toast = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+                 $lines = $toast.GetElementsByTagName('text')
+                 $null = $lines.Item(0).AppendChild($toast.CreateTextNode({}))
+                 $null = $lines.Item(1).AppendChild($toast.CreateTextNode({}))
+                 [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier({}).Show([Windows.UI.Notifications.ToastNotification]::new($toast))",
+                powershell_string(title),
+                powershell_string(body),
+                powershell_string(POWERSHELL_APP_ID)
+            );
+            spawn_first(&[("powershell", vec!["-NoProfile".to_string(), "-Command".to_string(), script])], None)
+        } else {
+            spawn_first(&[("notify-send", vec![title.to_string(), body.to_string()])], None)
+        }
+    }
+}
+
+/// Quotes `text` as a PowerShell literal string.
+fn powershell_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Starts the first program that exists and reaps it in the background,
+/// deleting `scratch` once it is done with it.
+fn spawn_first(candidates: &[(&str, Vec<String>)], scratch: Option<PathBuf>) -> io::Result<()> {
+    let remove_scratch = move || {
+        if let Some(path) = scratch {
+            let _ = fs::remove_file(path);
+        }
+    };
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no alert program available");
+    for (program, args) in candidates {
+        match Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                thread::spawn(move || {
+                    let _ = child.wait();
+                    remove_scratch();
+                });
+                return Ok(());
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    remove_scratch();
+    Err(last_error)
+}
+
+#[derive(Clone, Debug)]
+pub struct Alerts {
+    backend: Arc<dyn AlertBackend>,
+    sound: AlertSound,
+    volume: u8,
+    silent: bool,
+}
+
+impl Alerts {
+    pub fn new(backend: Arc<dyn AlertBackend>) -> Self {
+        let settings = Settings::default();
+        Self {
+            backend,
+            sound: settings.alert_sound,
+            volume: settings.alert_volume,
+            silent: settings.silent,
+        }
+    }
+
+    pub fn system() -> Self {
+        Self::new(Arc::new(SystemBackend))
+    }
+
+    pub fn configure(&mut self, settings: &Settings) {
+        self.sound = settings.alert_sound.clone();
+        self.volume = settings.alert_volume.min(100);
+        self.silent = settings.silent;
+    }
+
+    pub fn sound(&self) -> &AlertSound {
+        &self.sound
+    }
+
+    pub fn set_sound(&mut self, sound: AlertSound) {
+        self.sound = sound;
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(100);
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    pub fn set_silent(&mut self, silent: bool) {
+        self.silent = silent;
+    }
+
+    /// Announces the end of `phase` with a sound and a notification.
+    pub fn phase_ended(&self, phase: Phase) {
+        if self.silent {
+            return;
+        }
+        let (title, body) = match phase {
+            Phase::Focus => ("Focus complete", "Time for a break."),
+            Phase::ShortBreak | Phase::LongBreak => ("Break is over", "Ready to focus again?"),
+        };
+        if let Err(e) = self.backend.notify(title, body) {
+            eprintln!("Could not show notification: {}", e);
+        }
+        self.preview();
+    }
+
+    /// Plays the configured sound at the configured volume.
+    pub fn preview(&self) {
+        if self.silent || self.volume == 0 {
+            return;
+        }
+        let result = self.sound_bytes().and_then(|sound| self.backend.play(&scale_volume(&sound, self.volume)));
+        if let Err(e) = result {
+            eprintln!("Could not play alert sound: {}", e);
+        }
+    }
+
+    fn sound_bytes(&self) -> io::Result<Vec<u8>> {
+        match &self.sound {
+            AlertSound::Chime => Ok(CHIME.to_vec()),
+            AlertSound::Bell => Ok(BELL.to_vec()),
+            AlertSound::Wood => Ok(WOOD.to_vec()),
+            AlertSound::Custom(path) => fs::read(path),
+        }
+    }
+}
+
+/// Scales the samples of a 16-bit PCM WAV file to `volume` percent. Anything
+/// else is returned untouched and plays at the player's own volume.
+fn scale_volume(wav: &[u8], volume: u8) -> Vec<u8> {
+    let mut out = wav.to_vec();
+    if volume >= 100 || wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return out;
+    }
+
+    let mut bits_per_sample = 0;
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let size = u32::from_le_bytes([wav[offset + 4], wav[offset + 5], wav[offset + 6], wav[offset + 7]]) as usize;
+        let body = offset + 8;
+        let end = (body + size).min(wav.len());
+
+        if id == b"fmt " && end >= body + 16 {
+            let format = u16::from_le_bytes([wav[body], wav[body + 1]]);
+            bits_per_sample = if format == 1 { u16::from_le_bytes([wav[body + 14], wav[body + 15]]) } else { 0 };
+        } else if id == b"data" && bits_per_sample == 16 {
+            let factor = volume as f32 / 100.0;
+            for sample in out[body..end].chunks_exact_mut(2) {
+                let value = i16::from_le_bytes([sample[0], sample[1]]) as f32 * factor;
+                sample.copy_from_slice(&(value as i16).to_le_bytes());
+            }
+            return out;
+        }
+        // Chunks are padded to an even size
+        offset = body + size + (size & 1);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct RecordingBackend {
+        played: Mutex<Vec<Vec<u8>>>,
+        notified: Mutex<Vec<String>>,
+    }
+
+    impl AlertBackend for RecordingBackend {
+        fn play(&self, sound: &[u8]) -> io::Result<()> {
+            self.played.lock().unwrap().push(sound.to_vec());
+            Ok(())
+        }
+
+        fn notify(&self, title: &str, _body: &str) -> io::Result<()> {
+            self.notified.lock().unwrap().push(title.to_string());
+            Ok(())
+        }
+    }
+
+    fn alerts(settings: Settings) -> (Alerts, Arc<RecordingBackend>) {
+        let backend = Arc::new(RecordingBackend::default());
+        let mut alerts = Alerts::new(backend.clone());
+        alerts.configure(&settings);
+        (alerts, backend)
+    }
+
+    fn wav(samples: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        out.extend_from_slice(&1u16.to_le_bytes()); // mono
+        out.extend_from_slice(&44_100u32.to_le_bytes());
+        out.extend_from_slice(&88_200u32.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&data);
+        out
+    }
+
+    #[test]
+    fn phase_end_plays_sound_and_notifies() {
+        let (alerts, backend) = alerts(Settings { alert_volume: 100, ..Settings::default() });
+        alerts.phase_ended(Phase::Focus);
+
+        assert_eq!(backend.played.lock().unwrap().as_slice(), &[CHIME.to_vec()]);
+        assert_eq!(backend.notified.lock().unwrap().as_slice(), &["Focus complete".to_string()]);
+    }
+
+    #[test]
+    fn silent_mode_fires_nothing() {
+        let (alerts, backend) = alerts(Settings { silent: true, ..Settings::default() });
+        alerts.phase_ended(Phase::ShortBreak);
+
+        assert!(backend.played.lock().unwrap().is_empty());
+        assert!(backend.notified.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn plays_the_selected_bundled_sound() {
+        let (alerts, backend) = alerts(Settings { alert_sound: AlertSound::Bell, alert_volume: 100, ..Settings::default() });
+        alerts.preview();
+        assert_eq!(backend.played.lock().unwrap()[0], BELL);
+    }
+
+    #[test]
+    fn plays_a_custom_sound_file() {
+        let path = std::env::temp_dir().join(format!("pomimi-test-custom-{}.wav", std::process::id()));
+        fs::write(&path, wav(&[1000, -1000])).unwrap();
+        let (alerts, backend) = alerts(Settings {
+            alert_sound: AlertSound::Custom(path.to_string_lossy().into_owned()),
+            alert_volume: 50,
+            ..Settings::default()
+        });
+        alerts.preview();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(backend.played.lock().unwrap()[0], wav(&[500, -500]));
+    }
+
+    #[test]
+    fn scales_pcm_samples_by_volume() {
+        assert_eq!(scale_volume(&wav(&[10_000, -20_000, 3]), 25), wav(&[2_500, -5_000, 0]));
+        assert_eq!(scale_volume(&wav(&[10_000]), 100), wav(&[10_000]));
+    }
+
+    #[test]
+    fn quotes_powershell_strings() {
+        assert_eq!(powershell_string(r"C:\Users\o'neil\a.wav"), r"'C:\Users\o''neil\a.wav'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn removes_the_scratch_file_once_the_player_exits() {
+        let scratch = |name: &str| {
+            let path = std::env::temp_dir().join(format!("pomimi-test-{}-{}.wav", name, std::process::id()));
+            fs::write(&path, b"RIFF").unwrap();
+            path
+        };
+
+        let played = scratch("played");
+        spawn_first(&[("true", Vec::new())], Some(played.clone())).unwrap();
+        for _ in 0..200 {
+            if !played.exists() {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(!played.exists());
+
+        let unplayed = scratch("unplayed");
+        assert!(spawn_first(&[("pomimi-no-such-player", Vec::new())], Some(unplayed.clone())).is_err());
+        assert!(!unplayed.exists());
+    }
+
+    #[test]
+    fn leaves_non_wav_data_untouched() {
+        assert_eq!(scale_volume(b"ID3 not a wav file", 10), b"ID3 not a wav file");
+    }
+}
@@ -0,0 +1,286 @@
+//! Command-line arguments. Without a subcommand pomimi opens the GUI.
+
+use crate::export::{Format, Table};
+use crate::model::{self, ImportFormat};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Parser)]
+#[command(name = "pomimi", version, about = "A Pomodoro timer and task manager")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Open the graphical interface (the default)
+    Gui,
+    /// Run a timer in the terminal; without a duration, pick a profile from a menu
+    Timer(TimerArgs),
+    /// Full-screen terminal interface with the timer and the task list
+    Tui,
+    /// Manage tasks
+    #[command(subcommand)]
+    Task(TaskCommand),
+    /// Manage projects and their colors
+    #[command(subcommand)]
+    Project(ProjectCommand),
+    /// Show focus statistics
+    Stats {
+        /// Compare pomodoro estimates with the pomodoros tasks took, week by week
+        #[arg(long)]
+        estimates: bool,
+        /// Weeks shown by --estimates, this one included
+        #[arg(long, default_value_t = 8, requires = "estimates", value_parser = clap::value_parser!(u32).range(1..))]
+        weeks: u32,
+    },
+    /// Export sessions or tasks as CSV or JSON
+    Export(ExportArgs),
+    /// Save a snapshot of the database; safe while pomimi is running
+    Backup {
+        /// Where to write it; a timestamped file in the backups folder by default
+        file: Option<PathBuf>,
+    },
+    /// Replace the database with a backup
+    Restore {
+        file: PathBuf,
+    },
+    /// Read or change settings
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Print a shell completion script
+    Completions {
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct TimerArgs {
+    /// Focus length, e.g. 25m, 90s or 1h (plain numbers are minutes)
+    #[arg(value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+    /// Short break length
+    #[arg(long = "break", value_name = "DURATION", value_parser = parse_duration)]
+    pub short_break: Option<Duration>,
+    /// Long break length
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub long_break: Option<Duration>,
+    /// Number of focus blocks to run; the timer stops after the break that
+    /// follows the last one
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub cycles: u32,
+    /// Start from a saved timer profile instead of the default one
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// Wait for Enter before each break and focus block
+    #[arg(long)]
+    pub require_input: bool,
+    /// Work on this task (see `pomimi task list` for ids)
+    #[arg(long, value_name = "ID")]
+    pub task: Option<i64>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ExportArgs {
+    #[arg(value_enum)]
+    pub table: Table,
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    pub format: Format,
+    /// First day to include, e.g. 2024-03-01
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<NaiveDate>,
+    /// Last day to include
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<NaiveDate>,
+    /// Write to a file instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Write timestamps with this machine's UTC offset instead of in UTC
+    #[arg(long)]
+    pub local_time: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TaskCommand {
+    /// Add a task
+    Add {
+        /// Task description
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+    },
+    /// List open tasks
+    List {
+        /// Show completed tasks instead
+        #[arg(long, conflicts_with = "archived")]
+        done: bool,
+        /// Show archived tasks instead
+        #[arg(long)]
+        archived: bool,
+        /// Only tasks in this project
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+        /// Only tasks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Change a task's name, notes, due date, priority, estimate, project or tags
+    Edit {
+        id: i64,
+        /// New name
+        #[arg(long)]
+        text: Option<String>,
+        /// Notes; an empty value clears them
+        #[arg(long)]
+        notes: Option<String>,
+        /// Due date as YYYY-MM-DD; an empty value clears it
+        #[arg(long, value_name = "DATE")]
+        due: Option<String>,
+        /// Priority from 1 (highest) to 3; 0 clears it
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=3))]
+        priority: Option<u8>,
+        /// Pomodoros the task should take; 0 clears it
+        #[arg(long, value_parser = clap::value_parser!(u32).range(0..=99))]
+        estimate: Option<u32>,
+        /// Project, created if it does not exist yet; an empty value clears it
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+        /// Tags separated by commas, replacing the current ones; an empty value clears them
+        #[arg(long)]
+        tags: Option<String>,
+    },
+    /// Mark a task as done
+    Done {
+        id: i64,
+    },
+    /// Move an open task to another place in the list
+    Move {
+        id: i64,
+        /// New place in the list, 1 being the top
+        #[arg(value_parser = clap::value_parser!(u32).range(1..))]
+        position: u32,
+    },
+    /// Delete a task
+    Rm {
+        id: i64,
+    },
+    /// Add tasks from a plain text, Markdown checklist or todo.txt file
+    Import {
+        /// File to read; standard input when left out or `-`
+        file: Option<PathBuf>,
+        /// Input format, detected from the contents when left out
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectCommand {
+    /// List projects with their colors
+    List,
+    /// Create a project
+    Add {
+        name: String,
+        /// Color like #4C9AFF; the next one from the palette by default
+        #[arg(long, value_parser = parse_color)]
+        color: Option<[u8; 3]>,
+    },
+    /// Change a project's color
+    Color {
+        name: String,
+        #[arg(value_parser = parse_color)]
+        color: [u8; 3],
+    },
+    /// Delete a project; its tasks are kept
+    Rm {
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print one setting, or all of them
+    Get {
+        key: Option<String>,
+    },
+    /// Change a setting
+    Set {
+        key: String,
+        value: String,
+    },
+}
+
+/// Parses a `#RRGGBB` color.
+pub fn parse_color(value: &str) -> Result<[u8; 3], String> {
+    model::parse_hex_color(value.trim()).ok_or_else(|| format!("invalid color '{}', expected something like #4C9AFF", value))
+}
+
+/// Parses `25m`, `90s`, `1h` or a bare number of minutes.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit) = match value.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&value[..i], c),
+        _ => (value, 'm'),
+    };
+    let amount: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}', expected something like 25m, 90s or 1h", value))?;
+    let secs = match unit {
+        's' => amount,
+        'm' => amount * 60,
+        'h' => amount * 3600,
+        _ => return Err(format!("unknown unit '{}' in '{}', use s, m or h", unit, value)),
+    };
+    if secs == 0 {
+        return Err("duration must be greater than zero".to_string());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("25m"), Ok(Duration::from_secs(25 * 60)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("15"), Ok(Duration::from_secs(15 * 60)));
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("items").is_err());
+    }
+
+    #[test]
+    fn task_names_are_not_mistaken_for_timers() {
+        let args = Args::try_parse_from(["pomimi", "task", "add", "items"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Task(TaskCommand::Add { .. }))));
+    }
+
+    #[test]
+    fn list_positions_start_at_one() {
+        assert!(Args::try_parse_from(["pomimi", "task", "move", "4", "0"]).is_err());
+        let args = Args::try_parse_from(["pomimi", "task", "move", "4", "1"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Task(TaskCommand::Move { id: 4, position: 1 }))));
+    }
+
+    #[test]
+    fn parses_project_colors() {
+        let args = Args::try_parse_from(["pomimi", "project", "add", "Work", "--color", "#4c9aff"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Project(ProjectCommand::Add { color: Some([0x4C, 0x9A, 0xFF]), .. }))
+        ));
+        assert!(Args::try_parse_from(["pomimi", "project", "color", "Work", "blue"]).is_err());
+    }
+}
@@ -1,91 +1,50 @@
-use std::env;
-use std::io::{self, Read, Write};
-use std::process::Command;
-use std::thread;
-use std::time::{Duration, Instant};
+use chrono::{DateTime, Local, TimeZone};
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, SystemTime};
+use crate::alerts::Alerts;
+use crate::args::TimerArgs;
+use crate::commands::runtime;
+use crate::model::{Database, Settings, Task, TimerProfile};
+use crate::terminal::{install_interrupt_handler, interrupted, Event, Input, Key, RawMode};
+use crate::timer::{Phase, Timer, TimerEvent};
 
-// --- Terminal Handling ---
-
-struct RawMode;
-
-impl RawMode {
-    fn enable() -> io::Result<Self> {
-        let status = Command::new("stty")
-            .arg("-icanon")
-            .arg("-echo")
-            .status()?;
+// --- Input Handling ---
 
-        if !status.success() {
-            return Err(io::Error::new(io::ErrorKind::Other, "Failed to set raw mode"));
+/// Blocks until an event arrives; `None` once Ctrl-C was pressed or input ended.
+fn wait_event(input: &Input) -> Option<Event> {
+    loop {
+        if let Some(event) = input.poll(Duration::from_millis(100)) {
+            return Some(event);
+        }
+        if interrupted() || input.is_closed() {
+            return None;
         }
-        Ok(RawMode)
-    }
-}
-
-impl Drop for RawMode {
-    fn drop(&mut self) {
-        let _ = Command::new("stty").arg("sane").status();
     }
 }
 
-// --- Input Handling ---
+// --- Time Helpers ---
 
-enum Key {
-    Up,
-    Down,
-    Char(char),
-    Enter,
-    Unknown,
+fn format_clock<Tz: TimeZone>(time: &DateTime<Tz>) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    time.format("%H:%M").to_string()
 }
 
-fn read_key() -> Key {
-    let mut buffer = [0; 1];
-    let mut stdin = io::stdin();
-
-    if stdin.read(&mut buffer).is_ok() {
-        match buffer[0] {
-            b'\n' | b'\r' => Key::Enter,
-            b'\x1b' => {
-                let mut seq = [0; 2];
-                if stdin.read(&mut seq).is_ok() {
-                    if seq[0] == b'[' {
-                        match seq[1] {
-                            b'A' => Key::Up,
-                            b'B' => Key::Down,
-                            _ => Key::Unknown,
-                        }
-                    } else {
-                        Key::Unknown
-                    }
-                } else {
-                    Key::Char('\x1b')
-                }
-            }
-            c => Key::Char(c as char),
-        }
-    } else {
-        Key::Unknown
-    }
+fn format_future_clock<Tz: TimeZone>(now: &DateTime<Tz>, add_seconds: u64) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    format_clock(&(now.clone() + chrono::Duration::seconds(add_seconds as i64)))
 }
 
-// --- Time Helpers ---
-
 fn get_current_time_str() -> String {
-    let output = Command::new("date")
-        .arg("+%H:%M")
-        .output()
-        .expect("Failed to execute date command");
-    String::from_utf8_lossy(&output.stdout).trim().to_string()
+    format_clock(&Local::now())
 }
 
 fn get_future_time_str(add_seconds: u64) -> String {
-    let arg = format!("-v+{}S", add_seconds);
-    let output = Command::new("date")
-        .arg(&arg)
-        .arg("+%H:%M")
-        .output()
-        .expect("Failed to execute date command");
-    String::from_utf8_lossy(&output.stdout).trim().to_string()
+    format_future_clock(&Local::now(), add_seconds)
 }
 
 // --- Graphics & Logic ---
@@ -122,6 +81,22 @@ fn show_cursor() {
     io::stdout().flush().unwrap();
 }
 
+/// Hides the cursor until dropped, however the screen is left.
+struct HiddenCursor;
+
+impl HiddenCursor {
+    fn new() -> Self {
+        hide_cursor();
+        HiddenCursor
+    }
+}
+
+impl Drop for HiddenCursor {
+    fn drop(&mut self) {
+        show_cursor();
+    }
+}
+
 fn format_duration(d: Duration) -> String {
     let secs = d.as_secs();
     format!("{:02}:{:02}", secs / 60, secs % 60)
@@ -150,137 +125,311 @@ fn draw_progress_bar(width: usize, progress: f32, start_color: Rgb, end_color: R
     io::stdout().flush().unwrap();
 }
 
-fn play_sound() {
-    let _ = Command::new("afplay")
-        .arg("/System/Library/Sounds/Glass.aiff")
-        .spawn();
+/// The database shared with the GUI, opened once per run on its own runtime.
+struct Store {
+    runtime: tokio::runtime::Runtime,
+    db: Database,
+}
+
+impl Store {
+    fn open() -> Result<Self, String> {
+        let runtime = runtime()?;
+        let db = runtime.block_on(Database::new()).map_err(|e| format!("could not open database: {}", e))?;
+        Ok(Store { runtime, db })
+    }
+
+    /// Writes focus blocks to the same `sessions` table the GUI uses, so both
+    /// report the same totals.
+    fn record(&self, event: &TimerEvent) {
+        let Some(session) = event.focus_session() else {
+            return;
+        };
+        if let Err(e) = self.runtime.block_on(self.db.add_session(&session)) {
+            eprintln!("Could not record session: {}", e);
+        }
+    }
+}
+
+/// Timer profiles and settings shared with the GUI, or the defaults when the
+/// database could not be opened.
+fn load_preferences(store: Option<&Store>) -> (Vec<TimerProfile>, Settings) {
+    let defaults = || (TimerProfile::defaults(), Settings::default());
+    let Some(Store { runtime, db }) = store else {
+        return defaults();
+    };
+    runtime
+        .block_on(async { Ok::<_, sqlx::Error>((db.get_profiles().await?, db.load_settings().await?)) })
+        .unwrap_or_else(|e| {
+            eprintln!("Could not load preferences, using defaults: {}", e);
+            defaults()
+        })
 }
 
-fn wait_for_user_approval() {
-    println!("\n\n\x1b[2mReady to start? [Press Enter]\x1b[0m");
-    let mut input = String::new();
-    let _ = io::stdin().read_line(&mut input);
+/// Open tasks in list order, or none when the database could not be opened.
+fn load_open_tasks(store: Option<&Store>) -> Vec<Task> {
+    let Some(Store { runtime, db }) = store else {
+        return Vec::new();
+    };
+    runtime.block_on(db.get_tasks()).unwrap_or_else(|e| {
+        eprintln!("Could not load tasks: {}", e);
+        Vec::new()
+    })
 }
 
-fn run_timer(duration: Duration, label: &str, require_approval: bool) {
-    if require_approval {
-        wait_for_user_approval();
+fn find_open_task(store: Option<&Store>, id: i64) -> Result<Task, String> {
+    let Store { runtime, db } = store.ok_or("could not open database")?;
+    let task = runtime.block_on(db.get_task(id)).map_err(|e| e.to_string())?;
+    match task {
+        Some(task) if task.completed_at.is_none() => Ok(task),
+        Some(_) => Err(format!("task {} is already done", id)),
+        None => Err(format!("no task with id {}", id)),
     }
+}
+
+fn load_alerts(settings: &Settings) -> Alerts {
+    let mut alerts = Alerts::system();
+    alerts.configure(settings);
+    alerts
+}
 
-    let start_instant = Instant::now();
+/// Waits for Enter or space. Returns `false` if the user quits instead. When
+/// input has ended nobody can answer, so the next phase just starts.
+fn wait_for_user_approval(input: &Input) -> bool {
+    if io::stdout().is_terminal() {
+        println!("\n\n\x1b[2mReady to start? [Press Enter, q to quit]\x1b[0m");
+    } else {
+        println!("Ready to start? [Press Enter, q to quit]");
+    }
+    loop {
+        match wait_event(input) {
+            Some(Event::Key(Key::Enter | Key::Char(' '))) => return true,
+            Some(Event::Key(Key::Char('q') | Key::Esc)) => return false,
+            Some(_) => {}
+            None => return !interrupted(),
+        }
+    }
+}
+
+fn phase_label(timer: &Timer) -> String {
+    // A break belongs to the focus block that just finished
+    let per_set = timer.profile().cycles_before_long_break.max(1);
+    let finished = match timer.phase() {
+        Phase::Focus => timer.cycles_completed(),
+        Phase::ShortBreak | Phase::LongBreak => timer.cycles_completed().saturating_sub(1),
+    };
+    format!(
+        "{} ({}m) {}/{}",
+        timer.phase().label(),
+        timer.total().as_secs() / 60,
+        finished % per_set + 1,
+        per_set
+    )
+}
+
+fn draw_timer_screen(start_time_str: &str, label: &str, task: Option<&Task>, predicted_end_str: &str, remaining: Duration, progress: f32) {
     let indigo = Rgb { r: 75, g: 0, b: 130 };
     let orange = Rgb { r: 253, g: 94, b: 83 };
     let width = 25;
 
-    let start_time_str = get_current_time_str();
-    let predicted_end_str = get_future_time_str(duration.as_secs());
+    clear_screen();
+    // Top Left: Start Time (No label)
+    println!("\x1b[2m{}\x1b[0m\n", start_time_str);
+
+    println!("\x1b[2mPOMIMI: {}\x1b[0m", label);
+    if let Some(task) = task {
+        println!("\x1b[2mTask: {}\x1b[0m", task.text);
+    }
+
+    // Time Remaining with Predicted End on Left (No label for End)
+    println!("\x1b[2m{}  Time Remaining: {}\x1b[0m\n", predicted_end_str, format_duration(remaining));
 
-    hide_cursor();
+    draw_progress_bar(width, progress, indigo, orange);
+    println!("\n\n\x1b[2m(space pause, s skip, +/- minutes, q quit)\x1b[0m");
+}
+
+/// Drives `timer` through its phases until the user quits, or until
+/// `stop_after` focus blocks and their breaks are done. Focus blocks count
+/// towards `task`.
+fn run_timer(store: Option<&Store>, mut timer: Timer, input: &Input, alerts: &Alerts, task: Option<&Task>, stop_after: Option<usize>) {
+    let record = |event: &TimerEvent| {
+        if let Some(store) = store {
+            store.record(event);
+        }
+    };
+    // Redirected output gets one line per phase instead of a redrawn screen
+    let plain = !io::stdout().is_terminal();
+    let _cursor = (!plain).then(HiddenCursor::new);
+
+    timer.set_task(task.map(|t| t.id));
+    timer.start(SystemTime::now());
+    let mut start_time_str = get_current_time_str();
+    let mut events = Vec::new();
+    if plain {
+        print_phase_line(&timer, task);
+    }
 
     loop {
-        let elapsed = start_instant.elapsed();
-        if elapsed >= duration {
+        let now = SystemTime::now();
+        if interrupted() {
             break;
         }
 
-        let remaining = duration - elapsed;
-        let progress = elapsed.as_secs_f32() / duration.as_secs_f32();
-
-        clear_screen();
-        // Top Left: Start Time (No label)
-        println!("\x1b[2m{}\x1b[0m\n", start_time_str);
-
-        println!("\x1b[2mPOMIMI: {}\x1b[0m", label);
+        events.extend(timer.tick(now));
+        for event in events.drain(..) {
+            record(&event);
+            match event {
+                TimerEvent::PhaseEnded { phase, completed, .. } => {
+                    if completed {
+                        if plain {
+                            println!("{}  {} done", get_current_time_str(), phase.label());
+                        } else {
+                            // Final state
+                            let label = format!("{} - DONE!", phase_label(&timer));
+                            draw_timer_screen(&start_time_str, &label, task, &get_current_time_str(), Duration::ZERO, 1.0);
+                        }
+                        alerts.phase_ended(phase);
+                    }
 
-        // Time Remaining with Predicted End on Left (No label for End)
-        println!("\x1b[2m{}  Time Remaining: {}\x1b[0m\n", predicted_end_str, format_duration(remaining));
+                    if phase != Phase::Focus && stop_after.is_some_and(|n| timer.cycles_completed() >= n) {
+                        if !plain {
+                            println!("\n");
+                        }
+                        return;
+                    }
+                }
+                TimerEvent::PhaseStarted { running, .. } => {
+                    if !running {
+                        if !wait_for_user_approval(input) {
+                            break;
+                        }
+                        timer.start(SystemTime::now());
+                    }
+                    start_time_str = get_current_time_str();
+                    if plain {
+                        print_phase_line(&timer, task);
+                    }
+                }
+            }
+        }
 
-        draw_progress_bar(width, progress, indigo, orange);
+        if !plain {
+            let remaining = timer.remaining(now);
+            let progress = timer.elapsed(now).as_secs_f32() / timer.total().as_secs_f32();
+            let mut label = phase_label(&timer);
+            if timer.is_paused() {
+                label.push_str(" - PAUSED");
+            }
+            draw_timer_screen(&start_time_str, &label, task, &get_future_time_str(remaining.as_secs()), remaining, progress);
+        }
 
-        thread::sleep(Duration::from_millis(100));
+        // Resizes need nothing beyond the redraw on the next pass
+        let Some(Event::Key(key)) = input.poll(Duration::from_millis(100)) else {
+            continue;
+        };
+        let now = SystemTime::now();
+        match key {
+            Key::Char(' ') => timer.toggle(now),
+            Key::Char('s') => events = timer.skip(now),
+            Key::Char('+') | Key::Char('=') => timer.extend(60),
+            Key::Char('-') => timer.extend(-60),
+            Key::Char('q') | Key::Esc => break,
+            _ => {}
+        }
     }
 
-    // Final state
-    clear_screen();
-    println!("\x1b[2m{}\x1b[0m\n", start_time_str);
-    println!("\x1b[2mPOMIMI: {} - DONE!\x1b[0m", label);
-    println!("\x1b[2m{}  Time Remaining: 00:00\x1b[0m\n", predicted_end_str);
-    draw_progress_bar(width, 1.0, indigo, orange);
-    println!("\n");
-
-    play_sound();
+    // Quit or interrupted: keep whatever was focused so far as an unfinished session
+    timer.reset(SystemTime::now()).iter().for_each(record);
+    println!("{}Stopped.", if plain { "" } else { "\n\n" });
+}
 
-    show_cursor();
+/// One line per phase for redirected output, e.g. a log file.
+fn print_phase_line(timer: &Timer, task: Option<&Task>) {
+    let task = task.map(|t| format!(" - {}", t.text)).unwrap_or_default();
+    println!(
+        "{}  {}{}, ends at {}",
+        get_current_time_str(),
+        phase_label(timer),
+        task,
+        get_future_time_str(timer.total().as_secs())
+    );
 }
 
-pub fn run() {
-    let args: Vec<String> = env::args().collect();
+/// `pomimi timer`: runs the requested timer directly, or shows the profile
+/// menu when neither a duration nor a profile was given.
+pub fn run(args: TimerArgs) -> Result<(), String> {
+    let store = Store::open()
+        .map_err(|e| eprintln!("Using default preferences, sessions will not be recorded: {}", e))
+        .ok();
+    let (profiles, settings) = load_preferences(store.as_ref());
+    let alerts = load_alerts(&settings);
+
+    let task = args.task.map(|id| find_open_task(store.as_ref(), id)).transpose()?;
+    let customized = args.duration.is_some() || args.short_break.is_some() || args.long_break.is_some();
+    let show_menu = !customized && args.profile.is_none();
+
+    install_interrupt_handler();
+    // Without a terminal, keys arrive as lines (see `terminal::Input`)
+    let _raw = match RawMode::enable() {
+        Ok(raw) => Some(raw),
+        Err(_) if show_menu => {
+            return Err("the timer menu needs a terminal; pass a duration or --profile instead".to_string())
+        }
+        Err(_) => None,
+    };
+    let input = Input::spawn();
 
-    // Check custom arg
-    // Note: When calling from main we might need to adjust how we parse args if we introduce flags
-    // But for now, if the user does `pomimi 15m`, args[1] is 15m.
-    // If we have `pomimi --cli 15m`, args[1] is --cli.
-    // We should handle this.
+    if show_menu {
+        run_menu(store.as_ref(), &profiles, &input, &alerts, task);
+        return Ok(());
+    }
 
-    let time_arg = if args.len() > 1 {
-        if args[1] == "--cli" && args.len() > 2 {
-            Some(&args[2])
-        } else if args[1] != "--cli" {
-             Some(&args[1])
-        } else {
-            None
-        }
-    } else {
-        None
+    let base = match &args.profile {
+        Some(name) => profiles
+            .iter()
+            .find(|p| &p.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+                format!("no timer profile named '{}', available: {}", name, names.join(", "))
+            })?,
+        None => profiles[0].clone(),
+    };
+    let profile = TimerProfile {
+        name: if customized { "Custom".to_string() } else { base.name.clone() },
+        focus_secs: args.duration.map_or(base.focus_secs, |d| d.as_secs()),
+        short_break_secs: args.short_break.map_or(base.short_break_secs, |d| d.as_secs()),
+        long_break_secs: args.long_break.map_or(base.long_break_secs, |d| d.as_secs()),
+        ..base
     };
 
-    if let Some(time_str) = time_arg {
-        let duration = if time_str.ends_with('m') {
-            let mins = time_str.trim_end_matches('m').parse::<u64>().unwrap_or(0);
-            Duration::from_secs(mins * 60)
-        } else if time_str.ends_with('s') {
-            let secs = time_str.trim_end_matches('s').parse::<u64>().unwrap_or(0);
-            Duration::from_secs(secs)
-        } else {
-             let mins = time_str.parse::<u64>().unwrap_or(0);
-             Duration::from_secs(mins * 60)
-        };
+    let timer = Timer::new(profile).with_auto_continue(!args.require_input);
+    run_timer(store.as_ref(), timer, &input, &alerts, task.as_ref(), Some(args.cycles as usize));
+    Ok(())
+}
 
-        if duration.as_secs() > 0 {
-             run_timer(duration, "Custom Focus", false);
-             return;
-        } else {
-            // If it failed to parse, maybe it's not a time string but a command?
-            // For now, keep original behavior
-            eprintln!("Invalid time format. Use '15m' or '30s'.");
-            return;
-        }
-    }
+fn run_menu(store: Option<&Store>, profiles: &[TimerProfile], input: &Input, alerts: &Alerts, mut task: Option<Task>) {
+    let tasks = load_open_tasks(store);
 
     // Menu State
+    let task_index = profiles.len();
+    let toggle_index = profiles.len() + 1;
+    let quit_index = profiles.len() + 2;
     let mut require_approval = false;
     let mut selection = 0;
 
-    let _raw = match RawMode::enable() {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("Could not enable raw mode: {}", e);
-            return;
-        }
-    };
-
-    hide_cursor();
+    let _cursor = HiddenCursor::new();
 
     loop {
         clear_screen();
         println!("\x1b[2mChoose a POMIMI time:\x1b[0m\n");
 
-        let items_display = [
-            "25/5 Classic",
-            "50/10 Long",
-            if require_approval { "Require Input: ON" } else { "Require Input: OFF" },
-            "Quit"
-        ];
+        let mut items_display: Vec<String> = profiles
+            .iter()
+            .map(|p| format!("{} {}", p.label(), p.name))
+            .collect();
+        items_display.push(format!("Task: {}", task.as_ref().map_or("None", |t| t.text.as_str())));
+        items_display.push(if require_approval { "Require Input: ON" } else { "Require Input: OFF" }.to_string());
+        items_display.push("Quit".to_string());
 
         for (i, label) in items_display.iter().enumerate() {
             if i == selection {
@@ -294,46 +443,133 @@ pub fn run() {
 
         io::stdout().flush().unwrap();
 
-        match read_key() {
+        let Some(event) = wait_event(input) else {
+            break;
+        };
+        let Event::Key(key) = event else {
+            // Resized: redraw
+            continue;
+        };
+        match key {
             Key::Up | Key::Char('k') => {
-                if selection > 0 {
-                    selection -= 1;
-                }
+                selection = selection.saturating_sub(1);
             }
-            Key::Down | Key::Char('j') => {
-                if selection < 3 {
-                    selection += 1;
-                }
+            Key::Down | Key::Char('j') if selection < quit_index => {
+                selection += 1;
+            }
+            Key::Home | Key::Char('g') => {
+                selection = 0;
+            }
+            Key::End | Key::Char('G') => {
+                selection = quit_index;
             }
             Key::Enter | Key::Char('a') | Key::Char('A') => {
-                match selection {
-                    0 => { // Classic
-                        drop(_raw);
-                        run_timer(Duration::from_secs(25 * 60), "Focus (25m)", false);
-                        run_timer(Duration::from_secs(5 * 60), "Break (5m)", require_approval);
-                        break;
-                    },
-                    1 => { // Long
-                        drop(_raw);
-                        run_timer(Duration::from_secs(50 * 60), "Focus (50m)", false);
-                        run_timer(Duration::from_secs(10 * 60), "Break (10m)", require_approval);
-                        break;
-                    },
-                    2 => { // Toggle
-                        require_approval = !require_approval;
-                    },
-                    3 => { // Quit
-                        break;
-                    },
-                    _ => {}
+                if selection < task_index {
+                    let timer = Timer::new(profiles[selection].clone()).with_auto_continue(!require_approval);
+                    run_timer(store, timer, input, alerts, task.as_ref(), None);
+                    break;
+                } else if selection == task_index {
+                    match pick_task(&tasks, task.as_ref(), input) {
+                        Some(picked) => task = picked.cloned(),
+                        None if interrupted() => break,
+                        None => {}
+                    }
+                } else if selection == toggle_index {
+                    require_approval = !require_approval;
+                } else {
+                    break;
                 }
             }
-            Key::Char('q') => {
+            Key::Char('q') | Key::Esc => {
                 break;
             }
             _ => {}
         }
     }
+}
+
+/// Lets the user choose one of `tasks` or none. Returns `None` when the
+/// picker is dismissed, `Some(None)` when "No task" is chosen.
+fn pick_task<'a>(tasks: &'a [Task], current: Option<&Task>, input: &Input) -> Option<Option<&'a Task>> {
+    let mut selection = current
+        .and_then(|c| tasks.iter().position(|t| t.id == c.id))
+        .map_or(0, |i| i + 1);
+
+    loop {
+        clear_screen();
+        println!("\x1b[2mWhat are you working on?\x1b[0m\n");
+
+        let labels = std::iter::once("No task").chain(tasks.iter().map(|t| t.text.as_str()));
+        for (i, label) in labels.enumerate() {
+            if i == selection {
+                println!("> {} \x1b[32m[Selected]\x1b[0m", label);
+            } else {
+                println!("  {}", label);
+            }
+        }
+        if tasks.is_empty() {
+            println!("\n\x1b[2mNo open tasks. Add one with `pomimi task add`.\x1b[0m");
+        }
+
+        println!("\n\x1b[2m(Use j/k/arrows to move, Enter to pick, q to go back)\x1b[0m");
+        io::stdout().flush().unwrap();
+
+        let Event::Key(key) = wait_event(input)? else {
+            continue;
+        };
+        match key {
+            Key::Up | Key::Char('k') => {
+                selection = selection.saturating_sub(1);
+            }
+            Key::Down | Key::Char('j') if selection < tasks.len() => {
+                selection += 1;
+            }
+            Key::Home | Key::Char('g') => {
+                selection = 0;
+            }
+            Key::End | Key::Char('G') => {
+                selection = tasks.len();
+            }
+            Key::Enter => {
+                return Some(selection.checked_sub(1).map(|i| &tasks[i]));
+            }
+            Key::Char('q') | Key::Esc => {
+                return None;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
 
-    show_cursor();
+    #[test]
+    fn formats_clock_with_zero_padding() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 5, 7, 4, 59).unwrap();
+        assert_eq!(format_clock(&time), "07:04");
+    }
+
+    #[test]
+    fn formats_clock_in_the_given_timezone() {
+        let sao_paulo = FixedOffset::west_opt(3 * 3600).unwrap();
+        let time = Utc.with_ymd_and_hms(2024, 3, 5, 1, 30, 0).unwrap().with_timezone(&sao_paulo);
+        assert_eq!(format_clock(&time), "22:30");
+    }
+
+    #[test]
+    fn future_clock_rolls_over_midnight() {
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let now = tokyo.with_ymd_and_hms(2024, 3, 5, 23, 50, 0).unwrap();
+        assert_eq!(format_future_clock(&now, 25 * 60), "00:15");
+    }
+
+    #[test]
+    fn future_clock_truncates_to_the_minute() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 12, 0, 30).unwrap();
+        assert_eq!(format_future_clock(&now, 29), "12:00");
+        assert_eq!(format_future_clock(&now, 30), "12:01");
+    }
 }
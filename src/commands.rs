@@ -0,0 +1,340 @@
+//! Non-interactive subcommands: tasks, projects, stats, export, backups,
+//! config and completions.
+
+use crate::args::{Args, Command, ConfigCommand, ExportArgs, ProjectCommand, TaskCommand};
+use crate::export;
+use crate::model::{self, parse_tags, parse_tasks, Database, DateRange, ImportFormat, LabelFilter, Project, TaskFilter, SETTING_KEYS};
+use clap::CommandFactory;
+use std::fs;
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// A runtime for driving the async database from synchronous CLI code.
+pub fn runtime() -> Result<tokio::runtime::Runtime, String> {
+    tokio::runtime::Runtime::new().map_err(|e| format!("could not start runtime: {}", e))
+}
+
+/// Runs `future` to completion on a throwaway runtime; the CLI is otherwise
+/// synchronous.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output, String> {
+    Ok(runtime()?.block_on(future))
+}
+
+/// Trims a task name given on the command line, rejecting blank ones.
+fn task_name(text: &str) -> Result<&str, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("the task name cannot be empty".to_string());
+    }
+    Ok(text)
+}
+
+async fn open_database() -> Result<Database, String> {
+    Database::new().await.map_err(|e| format!("could not open database: {}", e))
+}
+
+async fn find_project(db: &Database, name: &str) -> Result<Project, String> {
+    db.get_project(name).await.map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("no project named '{}'", name))
+}
+
+pub fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Task(task) => block_on(run_task(task))?,
+        Command::Project(project) => block_on(run_project(project))?,
+        Command::Stats { estimates, weeks } => block_on(run_stats(estimates.then_some(weeks)))?,
+        Command::Export(args) => block_on(run_export(args))?,
+        Command::Backup { file } => block_on(run_backup(file))?,
+        Command::Restore { file } => block_on(run_restore(&file))?,
+        Command::Config(config) => block_on(run_config(config))?,
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Args::command(), "pomimi", &mut io::stdout());
+            Ok(())
+        }
+        Command::Gui | Command::Timer(_) | Command::Tui => unreachable!("handled by main"),
+    }
+}
+
+async fn run_task(command: TaskCommand) -> Result<(), String> {
+    let db = open_database().await?;
+    match command {
+        TaskCommand::Add { text } => {
+            let text = text.join(" ");
+            let text = task_name(&text)?;
+            db.add_task(text).await.map_err(|e| e.to_string())?;
+            println!("Added: {}", text);
+        }
+        TaskCommand::List { done, archived, project, tag } => {
+            let filter = if done {
+                TaskFilter::Completed
+            } else if archived {
+                TaskFilter::Archived
+            } else {
+                TaskFilter::Open
+            };
+            let labels = LabelFilter {
+                project_id: match project {
+                    Some(name) => Some(find_project(&db, &name).await?.id),
+                    None => None,
+                },
+                tag,
+            };
+            let mut tasks = db.get_tasks_with(filter).await.map_err(|e| e.to_string())?;
+            tasks.retain(|task| labels.matches(task));
+            if tasks.is_empty() {
+                println!("No tasks.");
+            }
+            for task in tasks {
+                let mut details = Vec::new();
+                if let Some(priority) = task.priority {
+                    details.push(format!("P{}", priority));
+                }
+                if let Some(due) = task.due_date {
+                    details.push(format!("due {}", due));
+                }
+                match task.estimate {
+                    Some(estimate) => details.push(format!("{}/{} pomodoros", task.pomodoros, estimate)),
+                    None if task.pomodoros > 0 => details.push(format!("{} pomodoros", task.pomodoros)),
+                    None => {}
+                }
+                if !task.subtasks.is_empty() {
+                    details.push(format!("{}/{} steps", task.steps_done(), task.subtasks.len()));
+                }
+                if let Some(project) = &task.project {
+                    details.push(format!("+{}", project));
+                }
+                details.extend(task.tags.iter().map(|tag| format!("#{}", tag)));
+                if details.is_empty() {
+                    println!("{:>4}  {}", task.id, task.text);
+                } else {
+                    println!("{:>4}  {}  ({})", task.id, task.text, details.join(", "));
+                }
+            }
+        }
+        TaskCommand::Edit { id, text, notes, due, priority, estimate, project, tags } => {
+            let mut task = db.get_task(id).await.map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("no task with id {}", id))?;
+            if let Some(text) = text {
+                task.text = task_name(&text)?.to_string();
+            }
+            if let Some(notes) = notes {
+                task.notes = Some(notes.trim().to_string()).filter(|n| !n.is_empty());
+            }
+            if let Some(due) = due {
+                task.due_date = match due.trim() {
+                    "" => None,
+                    due => Some(chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                        .map_err(|_| format!("invalid due date '{}', expected YYYY-MM-DD", due))?),
+                };
+            }
+            if let Some(priority) = priority {
+                task.priority = Some(priority).filter(|p| *p > 0);
+            }
+            if let Some(estimate) = estimate {
+                task.estimate = Some(estimate).filter(|e| *e > 0);
+            }
+            db.update_task(&task).await.map_err(|e| e.to_string())?;
+            if let Some(project) = project {
+                let project_id = match project.trim() {
+                    "" => None,
+                    name => Some(db.save_project(name, None).await.map_err(|e| e.to_string())?.id),
+                };
+                db.set_task_project(id, project_id).await.map_err(|e| e.to_string())?;
+            }
+            if let Some(tags) = tags {
+                db.set_task_tags(id, &parse_tags(&tags)).await.map_err(|e| e.to_string())?;
+            }
+            println!("Updated: {}", task.text);
+        }
+        TaskCommand::Done { id } => {
+            match db.get_task(id).await.map_err(|e| e.to_string())? {
+                Some(task) if task.completed_at.is_none() => {
+                    db.complete_task(id).await.map_err(|e| e.to_string())?;
+                    println!("Completed: {}", task.text);
+                }
+                Some(_) => return Err(format!("task {} is already done", id)),
+                None => return Err(format!("no task with id {}", id)),
+            }
+        }
+        TaskCommand::Move { id, position } => {
+            let task = db.get_task(id).await.map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("no task with id {}", id))?;
+            if task.completed_at.is_some() {
+                return Err(format!("task {} is done, only open tasks can be moved", id));
+            }
+            db.move_task(id, position as usize - 1).await.map_err(|e| e.to_string())?;
+            println!("Moved: {}", task.text);
+        }
+        TaskCommand::Rm { id } => {
+            let task = db.get_task(id).await.map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("no task with id {}", id))?;
+            db.delete_task(id).await.map_err(|e| e.to_string())?;
+            println!("Deleted: {}", task.text);
+        }
+        TaskCommand::Import { file, format } => {
+            let input = match file.filter(|path| path.as_os_str() != "-") {
+                Some(path) => fs::read_to_string(&path).map_err(|e| format!("could not read {}: {}", path.display(), e))?,
+                None => {
+                    let mut input = String::new();
+                    io::stdin().read_to_string(&mut input).map_err(|e| format!("could not read standard input: {}", e))?;
+                    input
+                }
+            };
+            let tasks = parse_tasks(&input, format.unwrap_or_else(|| ImportFormat::detect(&input)));
+            let count = db.import_tasks(&tasks).await.map_err(|e| e.to_string())?;
+            println!("Imported {} {}", count, if count == 1 { "task" } else { "tasks" });
+        }
+    }
+    Ok(())
+}
+
+async fn run_project(command: ProjectCommand) -> Result<(), String> {
+    let db = open_database().await?;
+    match command {
+        ProjectCommand::List => {
+            let projects = db.get_projects().await.map_err(|e| e.to_string())?;
+            if projects.is_empty() {
+                println!("No projects.");
+            }
+            for project in projects {
+                println!("{}  {}", model::format_hex_color(project.color), project.name);
+            }
+        }
+        ProjectCommand::Add { name, color } => {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err("the project name cannot be empty".to_string());
+            }
+            if db.get_project(name).await.map_err(|e| e.to_string())?.is_some() {
+                return Err(format!("project '{}' already exists", name));
+            }
+            let project = db.save_project(name, color).await.map_err(|e| e.to_string())?;
+            println!("Added: {} ({})", project.name, model::format_hex_color(project.color));
+        }
+        ProjectCommand::Color { name, color } => {
+            let project = find_project(&db, &name).await?;
+            db.save_project(&project.name, Some(color)).await.map_err(|e| e.to_string())?;
+            println!("{} = {}", project.name, model::format_hex_color(color));
+        }
+        ProjectCommand::Rm { name } => {
+            let project = find_project(&db, &name).await?;
+            db.delete_project(project.id).await.map_err(|e| e.to_string())?;
+            println!("Deleted: {}", project.name);
+        }
+    }
+    Ok(())
+}
+
+/// Prints today's focus time, or with `estimate_weeks` the estimate report.
+async fn run_stats(estimate_weeks: Option<u32>) -> Result<(), String> {
+    let db = open_database().await?;
+    let Some(weeks) = estimate_weeks else {
+        let today = db.get_today_focus_time().await.map_err(|e| e.to_string())?;
+        println!("Focus time today: {:02}:{:02}", today / 3600, (today % 3600) / 60);
+        return Ok(());
+    };
+
+    let report = db.estimate_report(weeks).await.map_err(|e| e.to_string())?;
+    let ratio = |ratio: Option<f64>| ratio.map_or("-".to_string(), |r| format!("{:.2}x", r));
+    println!("{:<10}  {:>5}  {:>9}  {:>6}  {:>6}  {:>9}", "Week of", "Tasks", "Estimated", "Actual", "Ratio", "On target");
+    for week in &report {
+        println!(
+            "{:<10}  {:>5}  {:>9}  {:>6}  {:>6}  {:>9}",
+            week.start, week.tasks, week.estimated, week.actual, ratio(week.ratio()), week.on_target
+        );
+    }
+    let estimated: u32 = report.iter().map(|w| w.estimated).sum();
+    let actual: u32 = report.iter().map(|w| w.actual).sum();
+    if estimated > 0 {
+        println!("Overall: {} the estimate", ratio(Some(actual as f64 / estimated as f64)));
+    } else {
+        println!("No estimated tasks were finished in these weeks.");
+    }
+    Ok(())
+}
+
+async fn run_export(args: ExportArgs) -> Result<(), String> {
+    if let (Some(from), Some(to)) = (args.from, args.to) {
+        if from > to {
+            return Err(format!("--from {} is after --to {}", from, to));
+        }
+    }
+    let db = open_database().await?;
+    let range = DateRange { from: args.from, to: args.to };
+    let mut bytes = Vec::new();
+    export::export(&db, args.table, args.format, range, args.local_time, &mut bytes).await?;
+    match args.output {
+        Some(path) => fs::write(&path, bytes).map_err(|e| format!("could not write {}: {}", path.display(), e)),
+        None => io::stdout().write_all(&bytes).map_err(|e| e.to_string()),
+    }
+}
+
+/// A new file in the backups folder named after the current time.
+fn timestamped_backup(prefix: &str) -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y-%m-%d-%H%M%S");
+    let dir = model::backups_dir();
+    let mut path = dir.join(format!("{}-{}.db", prefix, stamp));
+    for n in 2.. {
+        if !path.exists() {
+            break;
+        }
+        path = dir.join(format!("{}-{}-{}.db", prefix, stamp, n));
+    }
+    path
+}
+
+async fn run_backup(file: Option<PathBuf>) -> Result<(), String> {
+    let db = open_database().await?;
+    let path = file.unwrap_or_else(|| timestamped_backup("pomimi"));
+    db.backup_to(&path).await.map_err(|e| e.to_string())?;
+    println!("Backed up to {}", path.display());
+    Ok(())
+}
+
+async fn run_restore(file: &Path) -> Result<(), String> {
+    model::check_backup(file).await.map_err(|e| e.to_string())?;
+    // Keep what is being replaced, in case the wrong file was picked
+    let db = open_database().await?;
+    let previous = timestamped_backup("before-restore");
+    db.backup_to(&previous).await.map_err(|e| format!("could not back up the current database: {}", e))?;
+    db.close().await;
+
+    model::restore(file, &model::database_path()).await.map_err(|e| e.to_string())?;
+    println!("Restored {}", file.display());
+    println!("The previous database was saved to {}", previous.display());
+    Ok(())
+}
+
+async fn run_config(command: ConfigCommand) -> Result<(), String> {
+    let db = open_database().await?;
+    match command {
+        ConfigCommand::Get { key: Some(key) } => {
+            let settings = db.load_settings().await.map_err(|e| e.to_string())?;
+            println!("{}", settings.get(&key).map_err(|e| e.to_string())?);
+        }
+        ConfigCommand::Get { key: None } => {
+            let settings = db.load_settings().await.map_err(|e| e.to_string())?;
+            for key in SETTING_KEYS {
+                println!("{} = {}", key, settings.get(key).map_err(|e| e.to_string())?);
+            }
+        }
+        ConfigCommand::Set { key, value } => {
+            db.set_setting(&key, &value).await.map_err(|e| e.to_string())?;
+            println!("{} = {}", key, value);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_blank_task_names() {
+        assert_eq!(task_name("  Write the report "), Ok("Write the report"));
+        assert!(task_name("   ").is_err());
+        assert!(task_name("").is_err());
+    }
+}
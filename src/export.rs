@@ -0,0 +1,298 @@
+//! Sessions and tasks as CSV or JSON, for `pomimi export` and the GUI's
+//! export buttons. Rows come out in database order and timestamps as RFC 3339
+//! in UTC, so the same data exports to the same bytes on any machine; the
+//! local offset is opt-in. The rows themselves come from `model::export_rows`.
+
+use crate::model::{Database, DateRange, SessionRecord, TaskRecord};
+use chrono::{Local, SecondsFormat, TimeZone, Utc};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Table {
+    Sessions,
+    Tasks,
+}
+
+impl Table {
+    pub const ALL: [Table; 2] = [Table::Sessions, Table::Tasks];
+
+    fn name(self) -> &'static str {
+        match self {
+            Table::Sessions => "sessions",
+            Table::Tasks => "tasks",
+        }
+    }
+}
+
+/// One exported row: its JSON form comes from `Serialize`, its CSV form
+/// from `fields`, in the order of `COLUMNS`.
+trait Row: Serialize {
+    const COLUMNS: &'static [&'static str];
+    fn fields(&self) -> Vec<String>;
+}
+
+#[derive(Serialize)]
+struct SessionRow<'a> {
+    id: i64,
+    start: String,
+    end: String,
+    duration_seconds: i64,
+    planned_seconds: Option<i64>,
+    task_id: Option<i64>,
+    task: Option<&'a str>,
+    phase: &'static str,
+    completed: bool,
+}
+
+impl Row for SessionRow<'_> {
+    const COLUMNS: &'static [&'static str] =
+        &["id", "start", "end", "duration_seconds", "planned_seconds", "task_id", "task", "phase", "completed"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.start.clone(),
+            self.end.clone(),
+            self.duration_seconds.to_string(),
+            optional(self.planned_seconds),
+            optional(self.task_id),
+            self.task.unwrap_or_default().to_string(),
+            self.phase.to_string(),
+            self.completed.to_string(),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct TaskRow<'a> {
+    id: i64,
+    text: &'a str,
+    created: String,
+    completed: Option<String>,
+    archived: Option<String>,
+    focus_seconds: i64,
+}
+
+impl Row for TaskRow<'_> {
+    const COLUMNS: &'static [&'static str] = &["id", "text", "created", "completed", "archived", "focus_seconds"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.text.to_string(),
+            self.created.clone(),
+            self.completed.clone().unwrap_or_default(),
+            self.archived.clone().unwrap_or_default(),
+            self.focus_seconds.to_string(),
+        ]
+    }
+}
+
+fn optional(value: Option<i64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn timestamp<Tz: TimeZone>(secs: i64, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    tz.timestamp_opt(secs, 0)
+        .single()
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, false))
+        .unwrap_or_default()
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_rows<R: Row>(rows: &[R], format: Format, out: &mut impl Write) -> io::Result<()> {
+    match format {
+        Format::Csv => {
+            writeln!(out, "{}", R::COLUMNS.join(","))?;
+            for row in rows {
+                let fields: Vec<String> = row.fields().iter().map(|f| csv_field(f)).collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, rows)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_sessions<Tz: TimeZone>(sessions: &[SessionRecord], format: Format, tz: &Tz, out: &mut impl Write) -> io::Result<()>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let rows: Vec<SessionRow> = sessions.iter().map(|s| SessionRow {
+        id: s.id,
+        start: timestamp(s.started_at, tz),
+        end: timestamp(s.ended_at, tz),
+        duration_seconds: s.duration_seconds,
+        planned_seconds: s.planned_seconds,
+        task_id: s.task_id,
+        task: s.task.as_deref(),
+        // Only focus blocks are recorded; breaks never become sessions
+        phase: "focus",
+        completed: s.completed,
+    }).collect();
+    write_rows(&rows, format, out)
+}
+
+fn write_tasks<Tz: TimeZone>(tasks: &[TaskRecord], format: Format, tz: &Tz, out: &mut impl Write) -> io::Result<()>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let rows: Vec<TaskRow> = tasks.iter().map(|t| TaskRow {
+        id: t.id,
+        text: &t.text,
+        created: timestamp(t.created_at, tz),
+        completed: t.completed_at.map(|secs| timestamp(secs, tz)),
+        archived: t.archived_at.map(|secs| timestamp(secs, tz)),
+        focus_seconds: t.focus_seconds,
+    }).collect();
+    write_rows(&rows, format, out)
+}
+
+/// Writes `table` within `range` to `out`, with timestamps in UTC unless
+/// `local_time` asks for this machine's offset.
+pub async fn export(db: &Database, table: Table, format: Format, range: DateRange, local_time: bool, out: &mut impl Write) -> Result<(), String> {
+    let written = match table {
+        Table::Sessions => {
+            let sessions = db.export_sessions(range).await.map_err(|e| e.to_string())?;
+            if local_time {
+                write_sessions(&sessions, format, &Local, out)
+            } else {
+                write_sessions(&sessions, format, &Utc, out)
+            }
+        }
+        Table::Tasks => {
+            let tasks = db.export_tasks(range).await.map_err(|e| e.to_string())?;
+            if local_time {
+                write_tasks(&tasks, format, &Local, out)
+            } else {
+                write_tasks(&tasks, format, &Utc, out)
+            }
+        }
+    };
+    written.map_err(|e| format!("could not write export: {}", e))
+}
+
+/// Exports every table to `dir` as `pomimi-<table>-<today>.<ext>` and
+/// returns the files written.
+pub async fn export_to_dir(db: &Database, format: Format, range: DateRange, dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let today = Local::now().format("%Y-%m-%d");
+    let mut paths = Vec::new();
+    for table in Table::ALL {
+        let mut bytes = Vec::new();
+        export(db, table, format, range, false, &mut bytes).await?;
+        let path = dir.join(format!("pomimi-{}-{}.{}", table.name(), today, format.extension()));
+        fs::write(&path, bytes).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Where the GUI saves exports: the Downloads folder, or home without one.
+pub fn default_dir() -> Option<PathBuf> {
+    let dirs = directories::UserDirs::new()?;
+    Some(dirs.download_dir().unwrap_or(dirs.home_dir()).to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn session(id: i64, task: Option<&str>, completed: bool) -> SessionRecord {
+        SessionRecord {
+            id,
+            started_at: 1_709_640_000 + id * 3600,
+            ended_at: 1_709_640_000 + id * 3600 + 1500,
+            duration_seconds: 1500,
+            planned_seconds: Some(1500),
+            task_id: task.map(|_| 7),
+            task: task.map(str::to_string),
+            completed,
+        }
+    }
+
+    fn render(format: Format, write: impl Fn(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        write(&mut out).unwrap();
+        assert_eq!(format == Format::Json, out.starts_with(b"["));
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_sessions_as_csv() {
+        let tz = FixedOffset::west_opt(3 * 3600).unwrap();
+        let sessions = [session(0, Some("Write \"the\" report, v2"), true), session(1, None, false)];
+        let csv = render(Format::Csv, |out| write_sessions(&sessions, Format::Csv, &tz, out));
+        assert_eq!(
+            csv,
+            "id,start,end,duration_seconds,planned_seconds,task_id,task,phase,completed\n\
+             0,2024-03-05T09:00:00-03:00,2024-03-05T09:25:00-03:00,1500,1500,7,\"Write \"\"the\"\" report, v2\",focus,true\n\
+             1,2024-03-05T10:00:00-03:00,2024-03-05T10:25:00-03:00,1500,1500,,,focus,false\n"
+        );
+    }
+
+    #[test]
+    fn writes_utc_timestamps() {
+        assert_eq!(timestamp(1_709_640_000, &Utc), "2024-03-05T12:00:00+00:00");
+    }
+
+    #[test]
+    fn writes_tasks_as_json() {
+        let tz = FixedOffset::east_opt(0).unwrap();
+        let tasks = [TaskRecord {
+            id: 3,
+            text: "Plan".to_string(),
+            created_at: 1_709_640_000,
+            completed_at: Some(1_709_643_600),
+            archived_at: None,
+            focus_seconds: 3000,
+        }];
+        let json = render(Format::Json, |out| write_tasks(&tasks, Format::Json, &tz, out));
+        assert_eq!(
+            json,
+            r#"[
+  {
+    "id": 3,
+    "text": "Plan",
+    "created": "2024-03-05T12:00:00+00:00",
+    "completed": "2024-03-05T13:00:00+00:00",
+    "archived": null,
+    "focus_seconds": 3000
+  }
+]
+"#
+        );
+    }
+}
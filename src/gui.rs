@@ -1,8 +1,19 @@
-use iced::{Element, Task, Theme, Subscription, time, Length, window, Size, Color};
-use iced::widget::{column, container, text, button, center, row, text_input, scrollable, Space, stack};
+use iced::{Element, Task, Theme, Subscription, time, Length, window, Size, Color, Point};
+use iced::widget::{canvas, checkbox, column, container, text, button, center, row, text_input, scrollable, Space, stack};
+use crate::alerts::Alerts;
+use crate::export;
 use crate::theme;
-use crate::model::{Database, Task as DbTask};
-use std::time::Duration;
+use crate::model::{parse_tags, parse_tasks, priority_bucket, AlertSound, LOWEST_PRIORITY, Database, DateRange, EstimateWeek, FocusStats, LabelFilter, Project, PROJECT_COLORS, ImportFormat, Settings, Task as DbTask, TaskFilter, ThemeMode, TimerProfile};
+use crate::timer::{Phase, Timer, TimerEvent};
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+mod charts;
+
+const MINI_SIZE: Size = Size::new(270.0, 136.0);
+/// Weeks of estimate accuracy shown in the Stats modal.
+const ESTIMATE_WEEKS: u32 = 4;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ViewMode {
@@ -10,21 +21,12 @@ pub enum ViewMode {
     Mini,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Phase {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileField {
     Focus,
     ShortBreak,
     LongBreak,
-}
-
-impl Phase {
-    fn duration_secs(&self) -> u64 {
-        match self {
-            Phase::Focus => 25 * 60,
-            Phase::ShortBreak => 5 * 60,
-            Phase::LongBreak => 30 * 60,
-        }
-    }
+    Cycles,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,46 +34,282 @@ pub enum Modal {
     None,
     AddTask,
     Settings,
+    Stats,
 }
 
+/// A task being edited in place in the task list, as typed so far.
 #[derive(Debug, Clone)]
-struct TimerState {
-    phase: Phase,
-    remaining_secs: u64,
-    total_secs: u64,
-    is_running: bool,
-    cycles_completed: usize,
+struct TaskDraft {
+    id: i64,
+    text: String,
+    notes: String,
+    /// `YYYY-MM-DD`, or empty for no due date.
+    due_date: String,
+    priority: Option<u8>,
+    estimate: Option<u32>,
+    /// Project name, or empty for none.
+    project: String,
+    /// Tags separated by commas or spaces.
+    tags: String,
+    /// New color for the project, if one was picked.
+    project_color: Option<[u8; 3]>,
+    error: Option<&'static str>,
 }
 
-impl Default for TimerState {
-    fn default() -> Self {
+impl TaskDraft {
+    fn new(task: &DbTask) -> Self {
         Self {
-            phase: Phase::Focus,
-            remaining_secs: Phase::Focus.duration_secs(),
-            total_secs: Phase::Focus.duration_secs(),
-            is_running: false,
-            cycles_completed: 0,
+            id: task.id,
+            text: task.text.clone(),
+            notes: task.notes.clone().unwrap_or_default(),
+            due_date: task.due_date.map(|d| d.to_string()).unwrap_or_default(),
+            priority: task.priority,
+            estimate: task.estimate,
+            project: task.project.clone().unwrap_or_default(),
+            tags: task.tags.join(", "),
+            project_color: None,
+            error: None,
+        }
+    }
+
+    /// Applies the draft to `task`, or explains what is wrong with it.
+    fn apply(&self, task: &DbTask) -> Result<DbTask, &'static str> {
+        let text = self.text.trim();
+        if text.is_empty() {
+            return Err("The task needs a name");
+        }
+        let due_date = match self.due_date.trim() {
+            "" => None,
+            due => Some(chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d").map_err(|_| "Due date must look like 2024-03-05")?),
+        };
+        let notes = self.notes.trim();
+        Ok(DbTask {
+            text: text.to_string(),
+            notes: (!notes.is_empty()).then(|| notes.to_string()),
+            due_date,
+            priority: self.priority,
+            estimate: self.estimate,
+            project: Some(self.project.trim().to_string()).filter(|p| !p.is_empty()),
+            tags: parse_tags(&self.tags),
+            ..task.clone()
+        })
+    }
+}
+
+fn rgb_color([r, g, b]: [u8; 3]) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+/// The task's project in its color followed by its tags; empty when it has
+/// neither.
+fn task_labels<'a>(task: &'a DbTask) -> Element<'a, Message> {
+    let mut labels = row![].spacing(6);
+    if let Some(project) = &task.project {
+        let color = task.project_rgb().map_or(theme::TEXT_DIM, rgb_color);
+        labels = labels.push(text(project).size(10).color(color));
+    }
+    for tag in &task.tags {
+        labels = labels.push(text(format!("#{}", tag)).size(10).color(theme::TEXT_DIM));
+    }
+    labels.into()
+}
+
+/// Estimates up to this many pomodoros are drawn as dots.
+const MAX_ESTIMATE_DOTS: u32 = 10;
+
+/// Completed pomodoros against the estimate, as filled and empty dots with
+/// any overrun added as `+2`.
+fn pomodoro_progress(task: &DbTask) -> Option<String> {
+    match task.estimate {
+        Some(estimate) if estimate <= MAX_ESTIMATE_DOTS => {
+            let done = task.pomodoros.min(estimate);
+            let mut dots = "●".repeat(done as usize) + &"○".repeat((estimate - done) as usize);
+            if task.pomodoros > estimate {
+                dots.push_str(&format!(" +{}", task.pomodoros - estimate));
+            }
+            Some(dots)
         }
+        Some(estimate) => Some(format!("{}/{} pomodoros", task.pomodoros, estimate)),
+        None if task.pomodoros > 0 => Some(format!("{} pomodoros", task.pomodoros)),
+        None => None,
     }
 }
 
+/// The line under a task's name: whether it is active, then its priority,
+/// pomodoros, steps, due date and the start of its notes.
+fn task_details_line(task: &DbTask, is_active: bool) -> String {
+    let mut parts = vec![if is_active { "Active Task" } else { "Focus on this task" }.to_string()];
+    if let Some(priority) = task.priority {
+        parts.push(format!("P{}", priority));
+    }
+    parts.extend(pomodoro_progress(task));
+    if !task.subtasks.is_empty() {
+        parts.push(format!("{}/{} steps", task.steps_done(), task.subtasks.len()));
+    }
+    if let Some(due) = task.due_date {
+        let today = chrono::Local::now().date_naive();
+        parts.push(match due.signed_duration_since(today).num_days() {
+            0 => "Due today".to_string(),
+            1 => "Due tomorrow".to_string(),
+            days if days < 0 => format!("Overdue since {}", due.format("%b %d")),
+            _ => format!("Due {}", due.format("%b %d")),
+        });
+    }
+    if let Some(line) = task.notes.as_deref().and_then(|notes| notes.lines().next()) {
+        parts.push(line.to_string());
+    }
+    parts.join(" · ")
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
     db: Database,
     tasks: Vec<DbTask>,
-    timer: TimerState,
+    /// Done or archived tasks, loaded while `task_filter` is not `Open`.
+    filtered_tasks: Vec<DbTask>,
+    task_filter: TaskFilter,
+    /// Last task marked done, offered for undo until the next task action.
+    undo_task_id: Option<i64>,
+    timer: Timer,
+    /// Wall-clock time of the last tick, used to render the countdown.
+    now: SystemTime,
+    profiles: Vec<TimerProfile>,
+    selected_profile: usize,
+    /// Focus time already stored today; the running block is added on top.
     session_focus_seconds: i64,
     view_mode: ViewMode,
     new_task_input: String,
     active_task_id: Option<i64>,
+    /// Open task whose row is showing the edit form.
+    editing: Option<TaskDraft>,
+    /// Open task whose checklist is showing.
+    expanded_task: Option<i64>,
+    new_step_input: String,
+    /// Open tasks are shown in P1-P3 buckets.
+    group_by_priority: bool,
+    projects: Vec<Project>,
+    /// Tags in use, for the filter bar.
+    tags: Vec<String>,
+    /// Project and tag picked in the filter bar.
+    label_filter: LabelFilter,
     active_modal: Modal,
     primary_color: Color,
     is_dark_mode: bool,
+    window_size: Size,
+    mini_position: Option<Point>,
+    alerts: Alerts,
+    custom_sound_input: String,
+    /// Local hour at which focus totals roll over to a new day.
+    day_start_hour: u8,
+    /// Days covered by the Stats modal, 7 or 30.
+    stats_days: u32,
+    /// Loaded when the Stats modal opens.
+    stats: Option<FocusStats>,
+    /// Estimate accuracy for the last few weeks, loaded with `stats`.
+    estimates: Vec<EstimateWeek>,
+    /// Outcome of the last export, shown in the Stats modal.
+    export_status: Option<String>,
+}
+
+impl State {
+    fn profile(&self) -> &TimerProfile {
+        &self.profiles[self.selected_profile]
+    }
+
+    /// Reloads the project and tag lists for the filter bar.
+    fn reload_labels(&self) -> Task<Message> {
+        let db = self.db.clone();
+        Task::perform(
+            async move {
+                let projects = db.get_projects().await.map_err(|e| e.to_string())?;
+                let tags = db.get_tags().await.map_err(|e| e.to_string())?;
+                Ok((projects, tags))
+            },
+            Message::LabelsLoaded
+        )
+    }
+
+    /// Reloads the open tasks, plus the done/archived list when it is shown.
+    fn reload_tasks(&self) -> Task<Message> {
+        let db = self.db.clone();
+        let open = Task::perform(
+            async move { db.get_tasks().await.map_err(|e| e.to_string()) },
+            Message::TasksLoaded
+        );
+        if self.task_filter == TaskFilter::Open {
+            return open;
+        }
+        let db = self.db.clone();
+        let filter = self.task_filter;
+        Task::batch(vec![
+            open,
+            Task::perform(
+                async move { db.get_tasks_with(filter).await.map_err(|e| e.to_string()) },
+                Message::FilteredTasksLoaded
+            ),
+        ])
+    }
+
+    /// Applies timer events: finished or interrupted focus blocks are stored
+    /// against the task that was active when they started.
+    fn handle_timer_events(&mut self, now: SystemTime, events: Vec<TimerEvent>) -> Task<Message> {
+        self.now = now;
+        for event in &events {
+            if let TimerEvent::PhaseEnded { phase, completed: true, .. } = event {
+                self.alerts.phase_ended(*phase);
+            }
+        }
+        Task::batch(events.into_iter().map(|event| self.record_focus_block(event)))
+    }
+
+    fn reload_stats(&self) -> Task<Message> {
+        let db = self.db.clone();
+        let days = self.stats_days;
+        let estimates_db = self.db.clone();
+        Task::batch(vec![
+            Task::perform(
+                async move { db.focus_stats(days).await.map_err(|e| e.to_string()) },
+                Message::StatsLoaded
+            ),
+            Task::perform(
+                async move { estimates_db.estimate_report(ESTIMATE_WEEKS).await.map_err(|e| e.to_string()) },
+                Message::EstimatesLoaded
+            ),
+        ])
+    }
+
+    /// Focus seconds spent in the block that is currently running or paused.
+    fn running_focus_secs(&self) -> i64 {
+        if self.timer.phase() == Phase::Focus {
+            self.timer.elapsed(self.now).min(self.timer.total()).as_secs() as i64
+        } else {
+            0
+        }
+    }
+
+    /// Makes `id` the active task; a focus block already running keeps
+    /// counting towards the task it started with.
+    fn select_task(&mut self, id: Option<i64>) {
+        self.active_task_id = id;
+        self.timer.set_task(id);
+    }
+
+    /// Returns `Task::none()` unless `event` ends a focus block with time spent in it.
+    fn record_focus_block(&self, event: TimerEvent) -> Task<Message> {
+        let Some(session) = event.focus_session() else {
+            return Task::none();
+        };
+        let db = self.db.clone();
+        Task::perform(
+            async move { db.add_session(&session).await.map_err(|e| e.to_string()) },
+            Message::SessionRecorded
+        )
+    }
 }
 
 pub enum PomimiApp {
     Loading,
-    Loaded(State),
+    Loaded(Box<State>),
     Error(String),
 }
 
@@ -81,30 +319,80 @@ pub enum Message {
     FontLoaded(Result<(), iced::font::Error>),
     DbConnected(Result<Database, String>),
     TasksLoaded(Result<Vec<DbTask>, String>),
+    FilteredTasksLoaded(Result<Vec<DbTask>, String>),
+    LabelsLoaded(Result<(Vec<Project>, Vec<String>), String>),
     SessionLoaded(Result<i64, String>),
+    PreferencesLoaded(Result<(Vec<TimerProfile>, Settings), String>),
+    PreferenceSaved(Result<(), String>),
+    SessionRecorded(Result<(), String>),
+    StatsLoaded(Result<FocusStats, String>),
+    EstimatesLoaded(Result<Vec<EstimateWeek>, String>),
     TaskOperationFailed(String),
     TaskOperationSuccess,
 
     // Timer
     ToggleTimer,
+    SkipPhase,
+    ResetTimer,
     Tick,
-    SetDuration(u64),
+    SelectProfile(usize),
+    AdjustProfile(ProfileField, i64),
 
     // Tasks
     UpdateNewTaskInput(String),
+    /// The input's value after a paste, which has its line breaks dropped.
+    TaskInputPasted(String),
+    /// The raw clipboard, to import pasted lines as separate tasks.
+    TaskPasteRead { before: String, clipboard: Option<String> },
     AddTask,
     DeleteTask(i64),
     MarkTaskDone(i64),
+    UndoTaskDone,
+    ReopenTask(i64),
+    ArchiveCompleted,
+    SetTaskFilter(TaskFilter),
     SetActiveTask(i64),
+    ToggleSteps(i64),
+    NewStepInputChanged(String),
+    AddStep,
+    /// Checks or unchecks a step of the active task.
+    SetStepDone { task_id: i64, step_id: i64, done: bool },
+    DeleteStep(i64),
+    EditTask(i64),
+    EditTaskText(String),
+    EditTaskNotes(String),
+    EditTaskDueDate(String),
+    EditTaskPriority(Option<u8>),
+    EditTaskProject(String),
+    EditTaskProjectColor([u8; 3]),
+    EditTaskTags(String),
+    AdjustTaskEstimate(i32),
+    SaveTaskEdit,
+    CancelTaskEdit,
+    /// Moves an open task to a new index in the list as shown.
+    MoveTask(i64, usize),
+    ToggleGroupByPriority,
+    SetLabelFilter(LabelFilter),
 
     // UI
     ToggleMiniMode,
     OpenModal(Modal),
     CloseModal,
+    SetStatsRange(u32),
+    Export(export::Format),
+    Exported(Result<Vec<PathBuf>, String>),
     SetColor(Color),
     ToggleTheme,
-
-    None,
+    SetAlertSound(AlertSound),
+    AdjustVolume(i16),
+    AdjustDayStart(i8),
+    ToggleSilent,
+    UpdateCustomSoundInput(String),
+    UseCustomSound,
+    PreviewSound,
+    WindowResized(Size),
+    WindowMoved(Point),
+    CloseRequested(window::Id),
 }
 
 impl PomimiApp {
@@ -136,9 +424,8 @@ impl PomimiApp {
             PomimiApp::Loading => "Pomimi".to_string(),
             PomimiApp::Error(_) => "Pomimi - Error".to_string(),
             PomimiApp::Loaded(state) => {
-                let mins = state.timer.remaining_secs / 60;
-                let secs = state.timer.remaining_secs % 60;
-                format!("Pomimi - {:02}:{:02}", mins, secs)
+                let remaining = state.timer.remaining(state.now).as_secs();
+                format!("Pomimi - {:02}:{:02}", remaining / 60, remaining % 60)
             }
         }
     }
@@ -163,43 +450,110 @@ impl PomimiApp {
                              Message::SessionLoaded
                         );
 
-                        *self = PomimiApp::Loaded(State {
+                        let load_preferences = Task::perform(
+                            {
+                                let db = db.clone();
+                                async move {
+                                    let profiles = db.get_profiles().await.map_err(|e| e.to_string())?;
+                                    let settings = db.load_settings().await.map_err(|e| e.to_string())?;
+                                    Ok((profiles, settings))
+                                }
+                            },
+                            Message::PreferencesLoaded
+                        );
+
+                        let profiles = TimerProfile::defaults();
+                        let settings = Settings::default();
+                        *self = PomimiApp::Loaded(Box::new(State {
                             db,
                             tasks: Vec::new(),
-                            timer: TimerState::default(),
+                            filtered_tasks: Vec::new(),
+                            task_filter: TaskFilter::Open,
+                            undo_task_id: None,
+                            timer: Timer::new(profiles[0].clone()),
+                            now: SystemTime::now(),
+                            profiles,
+                            selected_profile: 0,
                             session_focus_seconds: 0,
                             view_mode: ViewMode::Full,
                             new_task_input: String::new(),
                             active_task_id: None,
+                            editing: None,
+                            expanded_task: None,
+                            new_step_input: String::new(),
+                            group_by_priority: settings.group_by_priority,
+                            projects: Vec::new(),
+                            tags: Vec::new(),
+                            label_filter: LabelFilter::default(),
                             active_modal: Modal::None,
-                            primary_color: theme::ORANGE, // Default, TODO: Load from DB
+                            primary_color: theme::ORANGE,
                             is_dark_mode: true,
-                        });
+                            window_size: Size::new(settings.window_size.0, settings.window_size.1),
+                            mini_position: None,
+                            alerts: Alerts::system(),
+                            custom_sound_input: String::new(),
+                            day_start_hour: settings.day_start_hour,
+                            stats_days: 7,
+                            stats: None,
+                            estimates: Vec::new(),
+                            export_status: None,
+                        }));
 
-                        Task::batch(vec![load_tasks, load_session])
+                        Task::batch(vec![load_tasks, load_session, load_preferences])
                     }
                     Message::DbConnected(Err(e)) => {
                         *self = PomimiApp::Error(format!("Failed to connect to database: {}", e));
                         Task::none()
                     }
                     Message::FontLoaded(_) => Task::none(),
+                    Message::CloseRequested(id) => window::close(id),
                     _ => Task::none(),
                 }
             }
-            PomimiApp::Error(_) => Task::none(),
+            PomimiApp::Error(_) => match message {
+                Message::CloseRequested(id) => window::close(id),
+                _ => Task::none(),
+            },
             PomimiApp::Loaded(state) => {
                 match message {
                     Message::TasksLoaded(Ok(tasks)) => {
                         state.tasks = tasks;
-                        if state.active_task_id.is_none() && !state.tasks.is_empty() {
-                            state.active_task_id = Some(state.tasks[0].id);
+                        // Picking one mid-block would look like it owns the block
+                        let focusing = state.timer.is_started() && state.timer.phase() == Phase::Focus;
+                        if state.active_task_id.is_none() && !focusing && !state.tasks.is_empty() {
+                            state.select_task(Some(state.tasks[0].id));
                         }
-                        Task::none()
+                        state.reload_labels()
                     }
                     Message::TasksLoaded(Err(e)) => {
                         eprintln!("Failed to load tasks: {}", e);
                         Task::none()
                     }
+                    Message::FilteredTasksLoaded(Ok(tasks)) => {
+                        state.filtered_tasks = tasks;
+                        Task::none()
+                    }
+                    Message::LabelsLoaded(Ok((projects, tags))) => {
+                        // Drop filters whose project or tag is gone
+                        let filter = &mut state.label_filter;
+                        if filter.project_id.is_some_and(|id| !projects.iter().any(|p| p.id == id)) {
+                            filter.project_id = None;
+                        }
+                        if filter.tag.as_ref().is_some_and(|tag| !tags.contains(tag)) {
+                            filter.tag = None;
+                        }
+                        state.projects = projects;
+                        state.tags = tags;
+                        Task::none()
+                    }
+                    Message::LabelsLoaded(Err(e)) => {
+                        eprintln!("Failed to load projects and tags: {}", e);
+                        Task::none()
+                    }
+                    Message::FilteredTasksLoaded(Err(e)) => {
+                        eprintln!("Failed to load tasks: {}", e);
+                        Task::none()
+                    }
                     Message::SessionLoaded(Ok(secs)) => {
                         state.session_focus_seconds = secs;
                         Task::none()
@@ -208,80 +562,115 @@ impl PomimiApp {
                          eprintln!("Failed to load session: {}", e);
                          Task::none()
                     }
-                    Message::TaskOperationFailed(e) => {
-                        eprintln!("Task operation failed: {}", e);
+                    Message::PreferencesLoaded(Ok((profiles, settings))) => {
+                        state.profiles = profiles;
+                        state.selected_profile = settings.selected_profile.as_ref()
+                            .and_then(|name| state.profiles.iter().position(|p| &p.name == name))
+                            .unwrap_or(0);
+                        if !state.timer.is_started() {
+                            state.timer = Timer::new(state.profile().clone()).with_task(state.active_task_id);
+                        }
+                        let [r, g, b] = settings.accent_color;
+                        state.primary_color = Color::from_rgb8(r, g, b);
+                        state.is_dark_mode = settings.theme_mode == ThemeMode::Dark;
+                        state.mini_position = settings.mini_position.map(|(x, y)| Point::new(x, y));
+                        state.alerts.configure(&settings);
+                        state.day_start_hour = settings.day_start_hour;
+                        state.group_by_priority = settings.group_by_priority;
+                        if let AlertSound::Custom(path) = &settings.alert_sound {
+                            state.custom_sound_input = path.clone();
+                        }
+
+                        let size = Size::new(settings.window_size.0, settings.window_size.1);
+                        if size == state.window_size {
+                            Task::none()
+                        } else {
+                            state.window_size = size;
+                            window::latest().and_then(move |id| window::resize(id, size))
+                        }
+                    }
+                    Message::PreferencesLoaded(Err(e)) => {
+                        eprintln!("Failed to load preferences: {}", e);
                         Task::none()
                     }
-                    Message::TaskOperationSuccess => {
-                         let db = state.db.clone();
-                         Task::perform(
-                            async move { db.get_tasks().await.map_err(|e| e.to_string()) },
-                            Message::TasksLoaded
+                    Message::SessionRecorded(Ok(())) => {
+                        let db = state.db.clone();
+                        Task::perform(
+                            async move { db.get_today_focus_time().await.map_err(|e| e.to_string()) },
+                            Message::SessionLoaded
                         )
                     }
+                    Message::SessionRecorded(Err(e)) => {
+                        eprintln!("Failed to record session: {}", e);
+                        Task::none()
+                    }
+                    Message::PreferenceSaved(Ok(())) => Task::none(),
+                    Message::PreferenceSaved(Err(e)) => {
+                        eprintln!("Failed to save preferences: {}", e);
+                        Task::none()
+                    }
+                    Message::TaskOperationFailed(e) => {
+                        eprintln!("Task operation failed: {}", e);
+                        Task::none()
+                    }
+                    Message::TaskOperationSuccess => state.reload_tasks(),
 
                     // Timer
                     Message::ToggleTimer => {
-                        state.timer.is_running = !state.timer.is_running;
-                        Task::none()
+                        let now = SystemTime::now();
+                        // Settle any boundary that passed since the last tick first
+                        let events = state.timer.tick(now);
+                        state.timer.toggle(now);
+                        state.handle_timer_events(now, events)
+                    }
+                    Message::SkipPhase => {
+                        let now = SystemTime::now();
+                        let events = state.timer.skip(now);
+                        state.handle_timer_events(now, events)
+                    }
+                    Message::ResetTimer => {
+                        let now = SystemTime::now();
+                        let events = state.timer.reset(now);
+                        state.handle_timer_events(now, events)
                     }
                     Message::Tick => {
-                        if state.timer.is_running {
-                            if state.timer.remaining_secs > 0 {
-                                state.timer.remaining_secs -= 1;
-                                if state.timer.phase == Phase::Focus {
-                                    state.session_focus_seconds += 1;
-                                }
-                            } else {
-                                let completed_phase = state.timer.phase.clone();
-                                match completed_phase {
-                                    Phase::Focus => {
-                                        state.timer.cycles_completed += 1;
-                                        let db = state.db.clone();
-                                        let duration = completed_phase.duration_secs() as i64;
-                                        let _ = Task::perform(
-                                            async move { db.add_session(duration).await },
-                                            |_| Message::None
-                                        );
-
-                                        if state.timer.cycles_completed % 4 == 0 {
-                                            state.timer.phase = Phase::LongBreak;
-                                        } else {
-                                            state.timer.phase = Phase::ShortBreak;
-                                        }
-                                    }
-                                    Phase::ShortBreak | Phase::LongBreak => {
-                                        state.timer.phase = Phase::Focus;
-                                    }
-                                }
-                                state.timer.remaining_secs = state.timer.phase.duration_secs();
-                                state.timer.total_secs = state.timer.phase.duration_secs();
-                            }
+                        let now = SystemTime::now();
+                        let events = state.timer.tick(now);
+                        state.handle_timer_events(now, events)
+                    }
+                    Message::SelectProfile(index) => {
+                        if index < state.profiles.len() {
+                            let now = SystemTime::now();
+                            let events = state.timer.reset(now);
+                            let interrupted = state.handle_timer_events(now, events);
+                            state.selected_profile = index;
+                            state.timer = Timer::new(state.profile().clone()).with_task(state.active_task_id);
+                            let db = state.db.clone();
+                            let name = state.profile().name.clone();
+                            Task::batch(vec![
+                                interrupted,
+                                persist(async move { db.set_selected_profile(&name).await }),
+                            ])
+                        } else {
+                            Task::none()
                         }
-                        Task::none()
                     }
-                    Message::SetDuration(secs) => {
-                        // Custom logic for 50/10 vs 25/5 could go here,
-                        // but simplified: just setting focus duration and derived break?
-                        // Requirement says "50/10, 25/5".
-                        // If 50m (3000s) -> Break 10m.
-                        // If 25m (1500s) -> Break 5m.
-                        state.timer.is_running = false;
-                        state.timer.phase = Phase::Focus;
-                        state.timer.remaining_secs = secs;
-                        state.timer.total_secs = secs;
-                        // Note: actual break duration logic is in Tick when switching phase.
-                        // Ideally we should store config for cycle lengths.
-                        // For now, hardcoded standard or simple override.
-                        // Let's assume standard unless customized.
-                        // Since we just set remaining, we need to ensure next break is correct.
-                        // But Phase enum hardcodes durations.
-                        // If we want dynamic phases, we need to change Phase impl or TimerState.
-                        // For this task, I'll stick to visual buttons and maybe simple state update?
-                        // Let's keep it simple: 25/5 is default Phase logic.
-                        // 50/10 would require changing Phase definition or state.
-                        // I'll skip implementing full custom duration logic deeply for now to focus on UI request.
-                        Task::none()
+                    Message::AdjustProfile(field, delta) => {
+                        let index = state.selected_profile;
+                        let profile = &mut state.profiles[index];
+                        let step = |secs: u64| (secs as i64 + delta * 60).max(60) as u64;
+                        match field {
+                            ProfileField::Focus => profile.focus_secs = step(profile.focus_secs),
+                            ProfileField::ShortBreak => profile.short_break_secs = step(profile.short_break_secs),
+                            ProfileField::LongBreak => profile.long_break_secs = step(profile.long_break_secs),
+                            ProfileField::Cycles => {
+                                profile.cycles_before_long_break = (profile.cycles_before_long_break as i64 + delta).max(1) as usize;
+                            }
+                        }
+                        state.timer.set_profile(state.profile().clone());
+                        let db = state.db.clone();
+                        let profiles = state.profiles.clone();
+                        persist(async move { db.save_profiles(&profiles).await })
                     }
 
                     // Tasks
@@ -289,79 +678,253 @@ impl PomimiApp {
                         state.new_task_input = input;
                         Task::none()
                     }
+                    Message::TaskInputPasted(value) => {
+                        let before = std::mem::replace(&mut state.new_task_input, value);
+                        iced::clipboard::read().map(move |clipboard| Message::TaskPasteRead { before: before.clone(), clipboard })
+                    }
+                    Message::TaskPasteRead { before, clipboard } => {
+                        let clipboard = clipboard.unwrap_or_default();
+                        let tasks = parse_tasks(&clipboard, ImportFormat::detect(&clipboard));
+                        if tasks.len() < 2 {
+                            return Task::none();
+                        }
+                        // Several lines: add them all instead of one run-together task
+                        state.new_task_input = before;
+                        state.active_modal = Modal::None;
+                        let db = state.db.clone();
+                        task_operation(async move { db.import_tasks(&tasks).await.map(|_| ()) })
+                    }
                     Message::AddTask => {
                         if !state.new_task_input.trim().is_empty() {
                             let text = state.new_task_input.trim().to_string();
                             state.new_task_input.clear();
                             state.active_modal = Modal::None; // Close modal
                             let db = state.db.clone();
-                            Task::perform(
-                                async move { db.add_task(&text).await.map_err(|e| e.to_string()) },
-                                |res| match res {
-                                    Ok(_) => Message::TaskOperationSuccess,
-                                    Err(e) => Message::TaskOperationFailed(e),
-                                }
-                            )
+                            task_operation(async move { db.add_task(&text).await })
                         } else {
                             Task::none()
                         }
                     }
+                    Message::EditTask(id) => {
+                        state.editing = state.tasks.iter().find(|t| t.id == id).map(TaskDraft::new);
+                        Task::none()
+                    }
+                    Message::EditTaskText(value) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.text = value;
+                        }
+                        Task::none()
+                    }
+                    Message::EditTaskNotes(value) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.notes = value;
+                        }
+                        Task::none()
+                    }
+                    Message::EditTaskDueDate(value) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.due_date = value;
+                        }
+                        Task::none()
+                    }
+                    Message::EditTaskPriority(priority) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.priority = priority;
+                        }
+                        Task::none()
+                    }
+                    Message::EditTaskProject(value) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.project = value;
+                        }
+                        Task::none()
+                    }
+                    Message::EditTaskProjectColor(color) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.project_color = Some(color);
+                        }
+                        Task::none()
+                    }
+                    Message::EditTaskTags(value) => {
+                        if let Some(draft) = &mut state.editing {
+                            draft.tags = value;
+                        }
+                        Task::none()
+                    }
+                    Message::AdjustTaskEstimate(delta) => {
+                        if let Some(draft) = &mut state.editing {
+                            let estimate = (draft.estimate.unwrap_or(0) as i32 + delta).clamp(0, 99) as u32;
+                            draft.estimate = (estimate > 0).then_some(estimate);
+                        }
+                        Task::none()
+                    }
+                    Message::SaveTaskEdit => {
+                        let Some(draft) = &mut state.editing else {
+                            return Task::none();
+                        };
+                        let Some(task) = state.tasks.iter_mut().find(|t| t.id == draft.id) else {
+                            state.editing = None;
+                            return Task::none();
+                        };
+                        let color = draft.project_color;
+                        match draft.apply(task) {
+                            Ok(edited) => {
+                                // Show the edit right away; the reload after saving confirms it
+                                *task = edited.clone();
+                                state.editing = None;
+                                let db = state.db.clone();
+                                task_operation(async move {
+                                    db.update_task(&edited).await?;
+                                    let project_id = match &edited.project {
+                                        Some(name) => Some(db.save_project(name, color).await?.id),
+                                        None => None,
+                                    };
+                                    db.set_task_project(edited.id, project_id).await?;
+                                    db.set_task_tags(edited.id, &edited.tags).await
+                                })
+                            }
+                            Err(error) => {
+                                draft.error = Some(error);
+                                Task::none()
+                            }
+                        }
+                    }
+                    Message::CancelTaskEdit => {
+                        state.editing = None;
+                        Task::none()
+                    }
+                    Message::MoveTask(id, index) => {
+                        let Some(from) = state.tasks.iter().position(|t| t.id == id) else {
+                            return Task::none();
+                        };
+                        // Reorder right away; the reload after saving confirms it
+                        let task = state.tasks.remove(from);
+                        state.tasks.insert(index.min(state.tasks.len()), task);
+                        let db = state.db.clone();
+                        task_operation(async move { db.move_task(id, index).await })
+                    }
+                    Message::SetLabelFilter(filter) => {
+                        state.label_filter = filter;
+                        Task::none()
+                    }
+                    Message::ToggleGroupByPriority => {
+                        state.group_by_priority = !state.group_by_priority;
+                        let grouped = state.group_by_priority;
+                        let db = state.db.clone();
+                        task_operation(async move { db.set_group_by_priority(grouped).await })
+                    }
                     Message::DeleteTask(id) => {
                         if state.active_task_id == Some(id) {
-                            state.active_task_id = None;
+                            state.select_task(None);
                         }
+                        state.undo_task_id = None;
                         let db = state.db.clone();
-                        Task::perform(
-                            async move { db.delete_task(id).await.map_err(|e| e.to_string()) },
-                            |res| match res {
-                                Ok(_) => Message::TaskOperationSuccess,
-                                Err(e) => Message::TaskOperationFailed(e),
-                            }
-                        )
+                        task_operation(async move { db.delete_task(id).await })
                     }
                     Message::MarkTaskDone(id) => {
                         if state.active_task_id == Some(id) {
-                            state.active_task_id = None;
+                            state.select_task(None);
                         }
-                         let db = state.db.clone();
-                        Task::perform(
-                            async move { db.delete_task(id).await.map_err(|e| e.to_string()) },
-                             |res| match res {
-                                Ok(_) => Message::TaskOperationSuccess,
-                                Err(e) => Message::TaskOperationFailed(e),
+                        state.undo_task_id = Some(id);
+                        let db = state.db.clone();
+                        task_operation(async move { db.complete_task(id).await })
+                    }
+                    Message::UndoTaskDone => {
+                        match state.undo_task_id.take() {
+                            Some(id) => {
+                                let db = state.db.clone();
+                                task_operation(async move { db.reopen_task(id).await })
                             }
-                        )
+                            None => Task::none(),
+                        }
+                    }
+                    Message::ReopenTask(id) => {
+                        state.undo_task_id = None;
+                        let db = state.db.clone();
+                        task_operation(async move { db.reopen_task(id).await })
+                    }
+                    Message::ArchiveCompleted => {
+                        state.undo_task_id = None;
+                        let db = state.db.clone();
+                        task_operation(async move { db.archive_completed().await })
+                    }
+                    Message::SetTaskFilter(filter) => {
+                        state.task_filter = filter;
+                        state.filtered_tasks.clear();
+                        state.reload_tasks()
                     }
                     Message::SetActiveTask(id) => {
-                        state.active_task_id = Some(id);
+                        state.select_task(Some(id));
+                        // Its steps are what gets checked off while working on it
+                        let has_steps = state.tasks.iter().any(|task| task.id == id && !task.subtasks.is_empty());
+                        if has_steps && state.expanded_task != Some(id) {
+                            state.expanded_task = Some(id);
+                            state.new_step_input.clear();
+                        }
                         Task::none()
                     }
+                    Message::ToggleSteps(id) => {
+                        state.expanded_task = if state.expanded_task == Some(id) { None } else { Some(id) };
+                        state.new_step_input.clear();
+                        Task::none()
+                    }
+                    Message::NewStepInputChanged(input) => {
+                        state.new_step_input = input;
+                        Task::none()
+                    }
+                    Message::AddStep => {
+                        let text = state.new_step_input.trim().to_string();
+                        let Some(task_id) = state.expanded_task.filter(|_| !text.is_empty()) else {
+                            return Task::none();
+                        };
+                        state.new_step_input.clear();
+                        let db = state.db.clone();
+                        task_operation(async move { db.add_subtask(task_id, &text).await })
+                    }
+                    Message::SetStepDone { task_id, step_id, done } => {
+                        if state.active_task_id != Some(task_id) {
+                            return Task::none();
+                        }
+                        // Check it off right away; the reload after saving confirms it
+                        if let Some(step) = state.tasks.iter_mut()
+                            .flat_map(|task| task.subtasks.iter_mut())
+                            .find(|step| step.id == step_id)
+                        {
+                            step.done = done;
+                        }
+                        let db = state.db.clone();
+                        task_operation(async move { db.set_subtask_done(step_id, done).await })
+                    }
+                    Message::DeleteStep(id) => {
+                        let db = state.db.clone();
+                        task_operation(async move { db.delete_subtask(id).await })
+                    }
 
                     // UI
                     Message::ToggleMiniMode => {
                         match state.view_mode {
                             ViewMode::Full => {
                                 state.view_mode = ViewMode::Mini;
-                                let mini_size = Size::new(270.0, 120.0);
-                                // Assume 1920x1080 or use a reasonable default for positioning
-                                // In iced 0.13, there's no direct way to get monitor size at runtime
-                                let x = 1920.0 - mini_size.width - 20.0; // Assume standard monitor width
-                                let y = 20.0;
+                                // Without a saved position, assume a 1920px wide monitor
+                                // and tuck the window into the top-right corner
+                                let position = state.mini_position
+                                    .unwrap_or(Point::new(1920.0 - MINI_SIZE.width - 20.0, 20.0));
                                 window::latest().and_then(move |id| {
                                     Task::batch(vec![
-                                        window::resize(id, mini_size),
+                                        window::resize(id, MINI_SIZE),
                                         window::set_level(id, window::Level::AlwaysOnTop),
                                         window::toggle_decorations(id),
                                         window::set_resizable(id, false),
-                                        window::move_to(id, iced::Point::new(x, y))
+                                        window::move_to(id, position)
                                     ])
                                 })
                             }
                             ViewMode::Mini => {
                                 state.view_mode = ViewMode::Full;
-                                window::latest().and_then(|id| {
+                                let size = state.window_size;
+                                window::latest().and_then(move |id| {
                                     Task::batch(vec![
-                                        window::resize(id, Size::new(380.0, 800.0)),
+                                        window::resize(id, size),
                                         window::set_level(id, window::Level::Normal),
                                         window::toggle_decorations(id),
                                         window::set_resizable(id, true)
@@ -371,7 +934,57 @@ impl PomimiApp {
                         }
                     }
                     Message::OpenModal(modal) => {
+                        state.export_status = None;
+                        let load = if modal == Modal::Stats { state.reload_stats() } else { Task::none() };
                         state.active_modal = modal;
+                        load
+                    }
+                    Message::SetStatsRange(days) => {
+                        state.stats_days = days;
+                        state.reload_stats()
+                    }
+                    Message::Export(format) => {
+                        // Same days as the charts, so the files match what is on screen
+                        let Some(stats) = &state.stats else {
+                            return Task::none();
+                        };
+                        let range = DateRange {
+                            from: stats.days.first().map(|(day, _)| *day),
+                            to: stats.days.last().map(|(day, _)| *day),
+                        };
+                        let db = state.db.clone();
+                        Task::perform(
+                            async move {
+                                let dir = export::default_dir().ok_or("no home folder to export to")?;
+                                export::export_to_dir(&db, format, range, &dir).await
+                            },
+                            Message::Exported
+                        )
+                    }
+                    Message::Exported(result) => {
+                        state.export_status = Some(match result {
+                            Ok(paths) => match paths.first().and_then(|path| path.parent()) {
+                                Some(dir) => format!("Saved {} files to {}", paths.len(), dir.display()),
+                                None => "Nothing exported".to_string(),
+                            },
+                            Err(e) => format!("Export failed: {}", e),
+                        });
+                        Task::none()
+                    }
+                    Message::StatsLoaded(Ok(stats)) => {
+                        state.stats = Some(stats);
+                        Task::none()
+                    }
+                    Message::EstimatesLoaded(Ok(estimates)) => {
+                        state.estimates = estimates;
+                        Task::none()
+                    }
+                    Message::EstimatesLoaded(Err(e)) => {
+                        eprintln!("Failed to load estimates: {}", e);
+                        Task::none()
+                    }
+                    Message::StatsLoaded(Err(e)) => {
+                        eprintln!("Failed to load stats: {}", e);
                         Task::none()
                     }
                     Message::CloseModal => {
@@ -380,13 +993,91 @@ impl PomimiApp {
                     }
                     Message::SetColor(color) => {
                         state.primary_color = color;
-                        // TODO: Persist
-                        Task::none()
+                        let [r, g, b, _] = color.into_rgba8();
+                        let db = state.db.clone();
+                        persist(async move { db.set_accent_color([r, g, b]).await })
                     }
                     Message::ToggleTheme => {
                         state.is_dark_mode = !state.is_dark_mode;
+                        let mode = if state.is_dark_mode { ThemeMode::Dark } else { ThemeMode::Light };
+                        let db = state.db.clone();
+                        persist(async move { db.set_theme_mode(mode).await })
+                    }
+                    Message::SetAlertSound(sound) => {
+                        state.alerts.set_sound(sound.clone());
+                        state.alerts.preview();
+                        let db = state.db.clone();
+                        persist(async move { db.set_alert_sound(&sound).await })
+                    }
+                    Message::AdjustDayStart(delta) => {
+                        let hour = (state.day_start_hour as i8 + delta).rem_euclid(24) as u8;
+                        state.day_start_hour = hour;
+                        let db = state.db.clone();
+                        // The boundary moved, so today's total and the stats move with it
+                        let reload_stats = if state.active_modal == Modal::Stats { state.reload_stats() } else { Task::none() };
+                        Task::batch(vec![
+                            Task::perform(
+                                async move {
+                                    db.set_day_start_hour(hour).await.map_err(|e| e.to_string())?;
+                                    db.get_today_focus_time().await.map_err(|e| e.to_string())
+                                },
+                                Message::SessionLoaded
+                            ),
+                            reload_stats,
+                        ])
+                    }
+                    Message::AdjustVolume(delta) => {
+                        let volume = (state.alerts.volume() as i16 + delta).clamp(0, 100) as u8;
+                        state.alerts.set_volume(volume);
+                        let db = state.db.clone();
+                        persist(async move { db.set_alert_volume(volume).await })
+                    }
+                    Message::ToggleSilent => {
+                        let silent = !state.alerts.is_silent();
+                        state.alerts.set_silent(silent);
+                        let db = state.db.clone();
+                        persist(async move { db.set_silent(silent).await })
+                    }
+                    Message::UpdateCustomSoundInput(input) => {
+                        state.custom_sound_input = input;
                         Task::none()
                     }
+                    Message::UseCustomSound => {
+                        let path = state.custom_sound_input.trim().to_string();
+                        if path.is_empty() {
+                            Task::none()
+                        } else {
+                            Task::done(Message::SetAlertSound(AlertSound::Custom(path)))
+                        }
+                    }
+                    Message::PreviewSound => {
+                        state.alerts.preview();
+                        Task::none()
+                    }
+                    Message::WindowResized(size) => {
+                        // Resizes in mini mode are our own doing; only remember the full layout
+                        if state.view_mode == ViewMode::Full && size != state.window_size {
+                            state.window_size = size;
+                            let db = state.db.clone();
+                            persist(async move { db.set_window_size(size.width, size.height).await })
+                        } else {
+                            Task::none()
+                        }
+                    }
+                    Message::CloseRequested(id) => {
+                        let now = SystemTime::now();
+                        let events = state.timer.reset(now);
+                        state.handle_timer_events(now, events).chain(window::close(id))
+                    }
+                    Message::WindowMoved(position) => {
+                        if state.view_mode == ViewMode::Mini && state.mini_position != Some(position) {
+                            state.mini_position = Some(position);
+                            let db = state.db.clone();
+                            persist(async move { db.set_mini_position(position.x, position.y).await })
+                        } else {
+                            Task::none()
+                        }
+                    }
 
                     _ => Task::none(),
                 }
@@ -412,13 +1103,25 @@ impl PomimiApp {
                 let content: Element<Message> = if state.view_mode == ViewMode::Mini {
                     let active_task_view: Element<'_, Message> = if let Some(id) = state.active_task_id {
                         if let Some(task) = state.tasks.iter().find(|t| t.id == id) {
+                             let mut details = column![
+                                 text(&task.text).size(12).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }),
+                             ];
+                             if let Some(step) = task.next_step() {
+                                 details = details.push(
+                                     row![
+                                         text(format!("Next: {}", step.text)).size(10).color(theme::TEXT_DIM).width(Length::Fill),
+                                         button(text("\u{e876}").font(iced::Font::with_name("Material Symbols Outlined")).size(10)) // check
+                                             .on_press(Message::SetStepDone { task_id: task.id, step_id: step.id, done: true })
+                                             .style(theme::button_ghost)
+                                             .padding(0),
+                                     ].spacing(5).align_y(iced::Alignment::Center)
+                                 );
+                             }
                              container(
                                  row![
                                      container(Space::new().width(6).height(6))
                                          .style(|_t: &Theme| container::Style { background: Some(state.primary_color.into()), ..container::Style::default() }),
-                                     column![
-                                         text(&task.text).size(12).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }),
-                                     ]
+                                     details,
                                  ].spacing(10).align_y(iced::Alignment::Center)
                              )
                              .padding(10)
@@ -437,7 +1140,7 @@ impl PomimiApp {
                         row![
                             timer_view,
                             Space::new().width(Length::Fill),
-                            button(text(if state.timer.is_running { "\u{e034}" } else { "\u{e037}" }).font(iced::Font::with_name("Material Symbols Outlined"))) // pause / play_arrow
+                            button(text(if state.timer.is_running() { "\u{e034}" } else { "\u{e037}" }).font(iced::Font::with_name("Material Symbols Outlined"))) // pause / play_arrow
                                 .on_press(Message::ToggleTimer).style(theme::button_secondary),
                             button(text("\u{e895}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // open_in_new / open_in_full icon
                                 .on_press(Message::ToggleMiniMode)
@@ -482,6 +1185,7 @@ impl PomimiApp {
                                 text("Add New Task").size(18).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }),
                                 text_input("What needs focus?", &state.new_task_input)
                                     .on_input(Message::UpdateNewTaskInput)
+                                    .on_paste(Message::TaskInputPasted)
                                     .on_submit(Message::AddTask)
                                     .padding(10),
                                 row![
@@ -502,10 +1206,18 @@ impl PomimiApp {
                                      button(container(Space::new().width(20).height(20)).style(|_: &Theme| container::Style{ background: Some(Color::from_rgb(0.5, 0.0, 1.0).into()), border: iced::Border{radius: 20.0.into(), ..iced::Border::default()}, ..container::Style::default() }))
                                         .on_press(Message::SetColor(Color::from_rgb(0.5, 0.0, 1.0))).style(theme::button_ghost),
                                  ].spacing(10),
+                                 text(format!("Timer Profile: {}", state.profile().name)).size(14),
+                                 self.view_profile_stepper("Focus", format!("{}m", state.profile().focus_secs / 60), ProfileField::Focus),
+                                 self.view_profile_stepper("Short Break", format!("{}m", state.profile().short_break_secs / 60), ProfileField::ShortBreak),
+                                 self.view_profile_stepper("Long Break", format!("{}m", state.profile().long_break_secs / 60), ProfileField::LongBreak),
+                                 self.view_profile_stepper("Cycles", state.profile().cycles_before_long_break.to_string(), ProfileField::Cycles),
+                                 self.view_alert_settings(state),
+                                 self.view_stepper("Day Starts At", format!("{:02}:00", state.day_start_hour), Message::AdjustDayStart(-1), Message::AdjustDayStart(1)),
                                  button(text("Close")).on_press(Message::CloseModal).style(theme::button_secondary).width(Length::Fill)
                             ].spacing(20)
                         },
-                        Modal::None => column![].into(),
+                        Modal::Stats => self.view_stats(state),
+                        Modal::None => column![],
                     };
 
                     let overlay = container(
@@ -532,15 +1244,16 @@ impl PomimiApp {
                         overlay
                     ].into()
                 } else {
-                    content.into()
+                    content
                 }
             }
         }
     }
 
     fn view_timer<'a>(&self, state: &'a State) -> Element<'a, Message> {
-        let mins = state.timer.remaining_secs / 60;
-        let secs = state.timer.remaining_secs % 60;
+        let remaining = state.timer.remaining(state.now).as_secs();
+        let mins = remaining / 60;
+        let secs = remaining % 60;
         let time_str = format!("{:02}:{:02}", mins, secs);
 
         let mut col = column![
@@ -551,12 +1264,15 @@ impl PomimiApp {
         ].align_x(iced::Alignment::Center);
 
         // Show strategy buttons only if NOT running
-        if !state.timer.is_running && state.view_mode == ViewMode::Full {
+        if !state.timer.is_running() && state.view_mode == ViewMode::Full {
              col = col.push(
-                 row![
-                     button(text("25/5").size(12)).on_press(Message::SetDuration(25*60)).style(theme::button_secondary).padding(5),
-                     button(text("50/10").size(12)).on_press(Message::SetDuration(50*60)).style(theme::button_secondary).padding(5),
-                 ].spacing(10).padding(10)
+                 row(state.profiles.iter().enumerate().map(|(i, profile)| {
+                     button(text(profile.label()).size(12))
+                         .on_press(Message::SelectProfile(i))
+                         .style(if i == state.selected_profile { theme::button_primary } else { theme::button_secondary })
+                         .padding(5)
+                         .into()
+                 })).spacing(10).padding(10)
              );
         }
 
@@ -565,7 +1281,7 @@ impl PomimiApp {
              col = col.push(
                  button(
                      row![
-                         text(if state.timer.is_running { "PAUSE FOCUS" } else { "START FOCUS" }).size(14).font(iced::Font::MONOSPACE).color(Color::BLACK),
+                         text(format!("{} {}", if state.timer.is_running() { "PAUSE" } else { "START" }, state.timer.phase().label().to_uppercase())).size(14).font(iced::Font::MONOSPACE).color(Color::BLACK),
                          text("\u{e5c8}").font(iced::Font::with_name("Material Symbols Outlined")).size(14).color(Color::BLACK) // arrow_forward
                      ].spacing(10).align_y(iced::Alignment::Center)
                  )
@@ -574,6 +1290,14 @@ impl PomimiApp {
                  .style(theme::button_primary)
                  .on_press(Message::ToggleTimer)
              );
+             if state.timer.is_started() {
+                 col = col.push(
+                     row![
+                         button(text("SKIP").size(12)).on_press(Message::SkipPhase).style(theme::button_ghost).padding(5),
+                         button(text("RESET").size(12)).on_press(Message::ResetTimer).style(theme::button_ghost).padding(5),
+                     ].spacing(10).padding(5)
+                 );
+             }
         }
 
         col.into()
@@ -583,15 +1307,59 @@ impl PomimiApp {
         let header = row![
             text("PRIORITY TASKS").size(12).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }).color(theme::TEXT_DIM),
             Space::new().width(Length::Fill),
+            button(text("BY PRIORITY").size(10).color(if state.group_by_priority { state.primary_color } else { theme::TEXT_DIM }))
+                .on_press(Message::ToggleGroupByPriority)
+                .style(theme::button_ghost)
+                .padding(2),
             button(text("+").size(14)).on_press(Message::OpenModal(Modal::AddTask)).style(theme::button_ghost)
         ].align_y(iced::Alignment::Center).width(Length::Fill);
 
-        let items: Element<'a, Message> = if state.tasks.is_empty() {
+        let filters = row(
+            [(TaskFilter::Open, "OPEN"), (TaskFilter::Completed, "COMPLETED"), (TaskFilter::Archived, "ARCHIVE")]
+                .into_iter()
+                .map(|(filter, label)| {
+                    let selected = state.task_filter == filter;
+                    button(text(label).size(10).color(if selected { state.primary_color } else { theme::TEXT_DIM }))
+                        .on_press(Message::SetTaskFilter(filter))
+                        .style(theme::button_ghost)
+                        .padding(2)
+                        .into()
+                })
+        ).spacing(10);
+
+        // Indexes stay those of the full list, which is what MoveTask expects
+        let visible: Vec<(usize, &DbTask)> = state.tasks.iter().enumerate().filter(|(_, task)| state.label_filter.matches(task)).collect();
+        let items: Element<'a, Message> = if state.task_filter != TaskFilter::Open {
+            self.view_done_tasks(state)
+        } else if state.tasks.is_empty() {
              container(text("No active tasks.").size(14).color(theme::TEXT_DIM)).width(Length::Fill).align_x(iced::Alignment::Center).padding(20).into()
+        } else if visible.is_empty() {
+             container(text("No tasks match the filter.").size(14).color(theme::TEXT_DIM)).width(Length::Fill).align_x(iced::Alignment::Center).padding(20).into()
         } else {
-             scrollable(column(
-                 state.tasks.iter().map(|task| {
-                     let is_active = state.active_task_id == Some(task.id);
+             let mut rows: Vec<Element<'a, Message>> = Vec::new();
+             for (position, &(_, task)) in visible.iter().enumerate() {
+                 let bucket = priority_bucket(task.priority);
+                 let previous = position.checked_sub(1).map(|i| visible[i]);
+                 let next = visible.get(position + 1).copied();
+                 if state.group_by_priority && previous.map(|(_, t)| priority_bucket(t.priority)) != Some(bucket) {
+                     let label = task.priority.map_or("NO PRIORITY".to_string(), |p| format!("P{}", p));
+                     rows.push(text(label).size(10).color(theme::TEXT_DIM).into());
+                 }
+                 // Tasks swap places with their visible neighbour; grouped
+                 // tasks only move within their own bucket
+                 let move_to = |neighbour: Option<(usize, &DbTask)>| {
+                     neighbour
+                         .filter(|(_, t)| !state.group_by_priority || priority_bucket(t.priority) == bucket)
+                         .map(|(index, _)| Message::MoveTask(task.id, index))
+                 };
+                 let move_up = move_to(previous);
+                 let move_down = move_to(next);
+                 if let Some(draft) = state.editing.as_ref().filter(|draft| draft.id == task.id) {
+                     rows.push(self.view_task_editor(state, draft));
+                     continue;
+                 }
+                 let is_active = state.active_task_id == Some(task.id);
+                 rows.push(
                      row![
                          // Checkbox Square (using button for now)
                          button(
@@ -604,14 +1372,30 @@ impl PomimiApp {
 
                          column![
                              text(&task.text).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }).width(Length::Fill),
-                             text(if is_active { "Active Task" } else { "Focus on this task" }).size(10).color(theme::TEXT_DIM)
-                         ].spacing(2).width(Length::Fill),
+                             text(task_details_line(task, is_active)).size(10).color(theme::TEXT_DIM)
+                         ].push(task_labels(task)).spacing(2).width(Length::Fill),
 
                          // Context Menu (Simplified to "More" or direct action for now, user asked for Dropdown but Iced simple dropdown is PickList which requires state.
                          // I'll implement a simple visibility toggle or just a delete/done button disguised as context for simplicity in this turn unless I add more state).
                          // Actually, requirements said "Dropdown list". I'll use a `pick_list` if possible, or just the buttons.
                          // Let's stick to the buttons but make them look minimal/icon only.
                          row![
+                             button(text("\u{e6b1}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // checklist
+                                .on_press(Message::ToggleSteps(task.id))
+                                .style(if state.expanded_task == Some(task.id) { theme::button_secondary } else { theme::button_ghost })
+                                .padding(5),
+                             button(text("\u{e5ce}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // move up
+                                .on_press_maybe(move_up)
+                                .style(theme::button_ghost)
+                                .padding(5),
+                             button(text("\u{e5cf}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // move down
+                                .on_press_maybe(move_down)
+                                .style(theme::button_ghost)
+                                .padding(5),
+                             button(text("\u{e3c9}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // edit
+                                .on_press(Message::EditTask(task.id))
+                                .style(theme::button_ghost)
+                                .padding(5),
                              button(text("\u{e876}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // done
                                 .on_press(Message::MarkTaskDone(task.id))
                                 .style(theme::button_ghost)
@@ -627,20 +1411,417 @@ impl PomimiApp {
                      .padding(10)
                      .width(Length::Fill)
                      .into()
-                 })
-             ).spacing(10)).height(Length::Fill).into()
+                 );
+                 if state.expanded_task == Some(task.id) {
+                     rows.push(self.view_steps(state, task, is_active));
+                 }
+             }
+             scrollable(column(rows).spacing(10)).height(Length::Fill).into()
+        };
+
+        let mut col = column![
+            header,
+            filters,
+        ].spacing(15);
+        if !state.projects.is_empty() || !state.tags.is_empty() {
+            col = col.push(self.view_label_filter(state));
+        }
+        col = col
+            .push(container(Space::new().height(1)).style(|_t: &Theme| container::Style { background: Some(theme::TEXT_DIM.into()), ..container::Style::default() }).width(Length::Fill))
+            .push(items);
+
+        if state.undo_task_id.is_some() {
+            col = col.push(
+                row![
+                    text("Task completed.").size(12).color(theme::TEXT_DIM),
+                    Space::new().width(Length::Fill),
+                    button(text("UNDO").size(12)).on_press(Message::UndoTaskDone).style(theme::button_ghost).padding(5),
+                ].align_y(iced::Alignment::Center)
+            );
+        }
+
+        col.into()
+    }
+
+    /// Chips narrowing the task lists to one project and/or one tag.
+    fn view_label_filter<'a>(&self, state: &'a State) -> Element<'a, Message> {
+        let filter = &state.label_filter;
+        let chip = |label: String, color: Color, selected: bool, target: LabelFilter| -> Element<'a, Message> {
+            button(text(label).size(10).color(color))
+                .on_press(Message::SetLabelFilter(target))
+                .style(if selected { theme::button_secondary } else { theme::button_ghost })
+                .padding(3)
+                .into()
+        };
+
+        let mut chips = vec![chip("ALL".to_string(), theme::TEXT_DIM, *filter == LabelFilter::default(), LabelFilter::default())];
+        for project in &state.projects {
+            let selected = filter.project_id == Some(project.id);
+            let target = LabelFilter { project_id: (!selected).then_some(project.id), ..filter.clone() };
+            chips.push(chip(project.name.clone(), rgb_color(project.color), selected, target));
+        }
+        for tag in &state.tags {
+            let selected = filter.tag.as_ref() == Some(tag);
+            let target = LabelFilter { tag: (!selected).then(|| tag.clone()), ..filter.clone() };
+            chips.push(chip(format!("#{}", tag), theme::TEXT_DIM, selected, target));
+        }
+        row(chips).spacing(6).wrap().into()
+    }
+
+    fn view_task_editor<'a>(&self, state: &'a State, draft: &'a TaskDraft) -> Element<'a, Message> {
+        let priorities = row(
+            std::iter::once(None).chain((1..=LOWEST_PRIORITY).map(Some)).map(|priority| {
+                let label = priority.map_or("NONE".to_string(), |p| format!("P{}", p));
+                button(text(label).size(10))
+                    .on_press(Message::EditTaskPriority(priority))
+                    .style(if draft.priority == priority { theme::button_primary } else { theme::button_secondary })
+                    .padding(5)
+                    .into()
+            })
+        ).spacing(6);
+
+        let mut form = column![
+            text_input("Task name", &draft.text)
+                .on_input(Message::EditTaskText)
+                .on_submit(Message::SaveTaskEdit)
+                .size(14)
+                .padding(5),
+            text_input("Notes", &draft.notes)
+                .on_input(Message::EditTaskNotes)
+                .on_submit(Message::SaveTaskEdit)
+                .size(12)
+                .padding(5),
+            row![
+                text_input("Due (YYYY-MM-DD)", &draft.due_date)
+                    .on_input(Message::EditTaskDueDate)
+                    .on_submit(Message::SaveTaskEdit)
+                    .size(12)
+                    .padding(5)
+                    .width(140),
+                Space::new().width(Length::Fill),
+                priorities,
+            ].spacing(10).align_y(iced::Alignment::Center),
+            row![
+                text("Estimated Pomodoros").size(12).color(theme::TEXT_DIM),
+                Space::new().width(Length::Fill),
+                button(text("-").size(12)).on_press(Message::AdjustTaskEstimate(-1)).style(theme::button_ghost).padding(5),
+                text(draft.estimate.map_or("-".to_string(), |e| e.to_string())).size(12).width(40).align_x(iced::Alignment::Center),
+                button(text("+").size(12)).on_press(Message::AdjustTaskEstimate(1)).style(theme::button_ghost).padding(5),
+            ].align_y(iced::Alignment::Center),
+            row![
+                text_input("Project", &draft.project)
+                    .on_input(Message::EditTaskProject)
+                    .on_submit(Message::SaveTaskEdit)
+                    .size(12)
+                    .padding(5)
+                    .width(140),
+                text_input("Tags", &draft.tags)
+                    .on_input(Message::EditTaskTags)
+                    .on_submit(Message::SaveTaskEdit)
+                    .size(12)
+                    .padding(5),
+            ].spacing(10).align_y(iced::Alignment::Center),
+        ].spacing(8);
+        if !draft.project.trim().is_empty() {
+            // The project's current color, unless a new one was picked
+            let current = draft.project_color.or_else(|| {
+                state.projects.iter().find(|p| p.name.eq_ignore_ascii_case(draft.project.trim())).map(|p| p.color)
+            });
+            let swatches = row(PROJECT_COLORS.into_iter().map(|color| {
+                let selected = current == Some(color);
+                button(Space::new().width(12).height(12))
+                    .on_press(Message::EditTaskProjectColor(color))
+                    .style(move |_t: &Theme, _status| button::Style {
+                        background: Some(rgb_color(color).into()),
+                        border: iced::Border { color: if selected { theme::WHITE } else { Color::TRANSPARENT }, width: 2.0, radius: 2.0.into() },
+                        ..button::Style::default()
+                    })
+                    .padding(0)
+                    .into()
+            })).spacing(6);
+            form = form.push(row![text("Project color").size(12).color(theme::TEXT_DIM).width(Length::Fill), swatches].align_y(iced::Alignment::Center));
+        }
+        if let Some(error) = draft.error {
+            form = form.push(text(error).size(10).color(state.primary_color));
+        }
+        form = form.push(
+            row![
+                Space::new().width(Length::Fill),
+                button(text("Cancel").size(12)).on_press(Message::CancelTaskEdit).style(theme::button_secondary).padding(5),
+                button(text("Save").size(12)).on_press(Message::SaveTaskEdit).style(theme::button_primary).padding(5),
+            ].spacing(8)
+        );
+
+        container(form)
+            .padding(10)
+            .width(Length::Fill)
+            .style(|_t: &Theme| container::Style { background: Some(Color { a: 0.05, ..theme::WHITE }.into()), ..container::Style::default() })
+            .into()
+    }
+
+    /// The checklist under an expanded task; steps can be checked off while
+    /// the task is the active one.
+    fn view_steps<'a>(&self, state: &'a State, task: &'a DbTask, is_active: bool) -> Element<'a, Message> {
+        let mut steps = column![].spacing(6);
+        for step in &task.subtasks {
+            let task_id = task.id;
+            let step_id = step.id;
+            steps = steps.push(
+                row![
+                    checkbox(step.done)
+                        .label(&step.text)
+                        .text_size(12)
+                        .size(14)
+                        .on_toggle_maybe(is_active.then_some(move |done| Message::SetStepDone { task_id, step_id, done }))
+                        .width(Length::Fill),
+                    button(text("\u{e5cd}").font(iced::Font::with_name("Material Symbols Outlined")).size(12)) // close
+                        .on_press(Message::DeleteStep(step.id))
+                        .style(theme::button_ghost)
+                        .padding(3),
+                ].align_y(iced::Alignment::Center)
+            );
+        }
+        if !task.subtasks.is_empty() && !is_active {
+            steps = steps.push(text("Make this the active task to check off steps").size(10).color(theme::TEXT_DIM));
+        }
+        steps = steps.push(
+            text_input("Add a step", &state.new_step_input)
+                .on_input(Message::NewStepInputChanged)
+                .on_submit(Message::AddStep)
+                .size(12)
+                .padding(5)
+        );
+
+        container(steps)
+            .padding(iced::Padding { left: 45.0, ..iced::Padding::new(0.0) })
+            .width(Length::Fill)
+            .into()
+    }
+
+    fn view_done_tasks<'a>(&self, state: &'a State) -> Element<'a, Message> {
+        let archived = state.task_filter == TaskFilter::Archived;
+        let tasks: Vec<&DbTask> = state.filtered_tasks.iter().filter(|task| state.label_filter.matches(task)).collect();
+        if tasks.is_empty() {
+            let empty = if state.filtered_tasks.is_empty() {
+                if archived { "Archive is empty." } else { "No completed tasks." }
+            } else {
+                "No tasks match the filter."
+            };
+            return container(text(empty).size(14).color(theme::TEXT_DIM)).width(Length::Fill).align_x(iced::Alignment::Center).padding(20).into();
+        }
+
+        let rows = column(
+            tasks.into_iter().map(|task| {
+                let completed = task.completed_at
+                    .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                    .map(|dt| format!("Completed {}", dt.with_timezone(&chrono::Local).format("%b %d, %H:%M")))
+                    .unwrap_or_default();
+
+                let mut actions = row![
+                    button(text("\u{e166}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // undo
+                        .on_press(Message::ReopenTask(task.id))
+                        .style(theme::button_ghost)
+                        .padding(5),
+                ];
+                if archived {
+                    actions = actions.push(
+                        button(text("\u{e872}").font(iced::Font::with_name("Material Symbols Outlined")).size(14)) // delete
+                            .on_press(Message::DeleteTask(task.id))
+                            .style(theme::button_ghost)
+                            .padding(5)
+                    );
+                }
+
+                row![
+                    column![
+                        text(&task.text).size(14).color(theme::TEXT_DIM).width(Length::Fill),
+                        text(completed).size(10).color(theme::TEXT_DIM)
+                    ].push(task_labels(task)).spacing(2).width(Length::Fill),
+                    actions
+                ]
+                .spacing(15)
+                .align_y(iced::Alignment::Center)
+                .padding(10)
+                .width(Length::Fill)
+                .into()
+            })
+        ).spacing(10);
+
+        let mut col = column![scrollable(rows).height(Length::Fill)].spacing(10);
+        if !archived {
+            col = col.push(
+                button(text("ARCHIVE ALL").size(12)).on_press(Message::ArchiveCompleted).style(theme::button_secondary).padding(5)
+            );
+        }
+        col.into()
+    }
+
+    fn view_alert_settings<'a>(&self, state: &'a State) -> Element<'a, Message> {
+        let sounds = row(AlertSound::BUNDLED.into_iter().map(|sound| {
+            let selected = state.alerts.sound() == &sound;
+            button(text(sound.name().to_uppercase()).size(10))
+                .on_press(Message::SetAlertSound(sound))
+                .style(if selected { theme::button_primary } else { theme::button_secondary })
+                .padding(5)
+                .into()
+        })).spacing(8);
+
+        let custom = row![
+            text_input("Custom .wav file", &state.custom_sound_input)
+                .on_input(Message::UpdateCustomSoundInput)
+                .on_submit(Message::UseCustomSound)
+                .size(12)
+                .padding(5),
+            button(text("USE").size(10))
+                .on_press(Message::UseCustomSound)
+                .style(if matches!(state.alerts.sound(), AlertSound::Custom(_)) { theme::button_primary } else { theme::button_secondary })
+                .padding(5),
+        ].spacing(8).align_y(iced::Alignment::Center);
+
+        column![
+            row![
+                text("Alert Sound").size(14).width(Length::Fill),
+                button(text("TEST").size(10)).on_press(Message::PreviewSound).style(theme::button_ghost).padding(5),
+                button(text(if state.alerts.is_silent() { "SILENT: ON" } else { "SILENT: OFF" }).size(10))
+                    .on_press(Message::ToggleSilent)
+                    .style(theme::button_ghost)
+                    .padding(5),
+            ].align_y(iced::Alignment::Center),
+            sounds,
+            custom,
+            row![
+                text("Volume").size(12).width(Length::Fill),
+                button(text("-").size(12)).on_press(Message::AdjustVolume(-10)).style(theme::button_ghost).padding(5),
+                text(format!("{}%", state.alerts.volume())).size(12).width(40).align_x(iced::Alignment::Center),
+                button(text("+").size(12)).on_press(Message::AdjustVolume(10)).style(theme::button_ghost).padding(5),
+            ].align_y(iced::Alignment::Center),
+        ].spacing(10).into()
+    }
+
+    fn view_profile_stepper<'a>(&self, label: &'a str, value: String, field: ProfileField) -> Element<'a, Message> {
+        self.view_stepper(label, value, Message::AdjustProfile(field, -1), Message::AdjustProfile(field, 1))
+    }
+
+    /// A labelled value with - and + buttons, as used in the settings.
+    fn view_stepper<'a>(&self, label: &'a str, value: String, decrease: Message, increase: Message) -> Element<'a, Message> {
+        row![
+            text(label).size(12).width(Length::Fill),
+            button(text("-").size(12)).on_press(decrease).style(theme::button_ghost).padding(5),
+            text(value).size(12).width(40).align_x(iced::Alignment::Center),
+            button(text("+").size(12)).on_press(increase).style(theme::button_ghost).padding(5),
+        ]
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
+    fn view_stats<'a>(&self, state: &'a State) -> iced::widget::Column<'a, Message> {
+        let range_button = |label: &'a str, days: u32| {
+            button(text(label).size(12))
+                .on_press(Message::SetStatsRange(days))
+                .style(if state.stats_days == days { theme::button_primary } else { theme::button_secondary })
+        };
+        let header = row![
+            text("Statistics").size(18).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }),
+            Space::new().width(Length::Fill),
+            range_button("7 days", 7),
+            range_button("30 days", 30),
+        ].spacing(8).align_y(iced::Alignment::Center);
+
+        let section = |title: &'a str| text(title).size(10).color(theme::TEXT_DIM).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT });
+
+        let body: Element<'a, Message> = match &state.stats {
+            None => text("Loading...").size(12).into(),
+            Some(stats) => {
+                let total: i64 = stats.days.iter().map(|(_, secs)| secs).sum();
+                let streak = match stats.streak {
+                    1 => "1 day streak".to_string(),
+                    days => format!("{} day streak", days),
+                };
+                let mut tasks = column![].spacing(6);
+                for task in stats.tasks.iter().take(5) {
+                    tasks = tasks.push(row![
+                        text(task.label()).size(12).width(Length::Fill),
+                        text(format!("{} · {}", charts::format_minutes(task.seconds), task.sessions)).size(12).color(theme::TEXT_DIM),
+                    ].spacing(10));
+                }
+                if stats.tasks.is_empty() {
+                    tasks = tasks.push(text("No focus sessions yet").size(12).color(theme::TEXT_DIM));
+                }
+
+                let mut projects = column![].spacing(6);
+                for project in &stats.projects {
+                    let color = project.rgb().map_or(theme::TEXT_DIM, rgb_color);
+                    projects = projects.push(row![
+                        text(project.label()).size(12).color(color).width(Length::Fill),
+                        text(format!("{} · {}", charts::format_minutes(project.seconds), project.sessions)).size(12).color(theme::TEXT_DIM),
+                    ].spacing(10));
+                }
+
+                let mut estimates = column![].spacing(6);
+                for week in state.estimates.iter().filter(|week| week.tasks > 0) {
+                    let ratio = week.ratio().map_or(String::new(), |r| format!(" · {:.2}×", r));
+                    estimates = estimates.push(row![
+                        text(format!("Week of {}", week.start.format("%b %d"))).size(12).width(Length::Fill),
+                        text(format!("{} of {} estimated{}", week.actual, week.estimated, ratio)).size(12).color(theme::TEXT_DIM),
+                    ].spacing(10));
+                }
+                if state.estimates.iter().all(|week| week.tasks == 0) {
+                    estimates = estimates.push(text("Finish tasks with an estimate to see how it compares").size(12).color(theme::TEXT_DIM));
+                }
+
+                let body = column![
+                    row![
+                        text(format!("{} focused", charts::format_minutes(total))).size(14),
+                        Space::new().width(Length::Fill),
+                        text(streak).size(14).color(state.primary_color),
+                    ],
+                    section("FOCUS PER DAY"),
+                    canvas(charts::DayChart { days: &stats.days, color: state.primary_color }).width(Length::Fill).height(120),
+                    section("BY HOUR OF DAY"),
+                    canvas(charts::HourHeatmap { hours: &stats.hours, color: state.primary_color }).width(Length::Fill).height(70),
+                    section("TASKS"),
+                    tasks,
+                ].spacing(10);
+                // Only worth a section once some sessions belong to a project
+                let body = if stats.projects.iter().any(|p| p.project.is_some()) {
+                    body.push(section("PROJECTS")).push(projects)
+                } else {
+                    body
+                };
+                body.extend([
+                    section("ESTIMATES").into(),
+                    estimates.into(),
+                ]).into()
+            }
+        };
+
+        let export_button = |label: &'a str, format: export::Format| {
+            let button = button(text(label).size(10)).style(theme::button_secondary).padding(5);
+            if state.stats.is_some() { button.on_press(Message::Export(format)) } else { button }
         };
+        let mut export_row = column![
+            row![
+                text("Export Sessions & Tasks").size(12).width(Length::Fill),
+                export_button("CSV", export::Format::Csv),
+                export_button("JSON", export::Format::Json),
+            ].spacing(8).align_y(iced::Alignment::Center),
+        ].spacing(6);
+        if let Some(status) = &state.export_status {
+            export_row = export_row.push(text(status).size(10).color(theme::TEXT_DIM));
+        }
 
         column![
             header,
-            container(Space::new().height(1)).style(|_t: &Theme| container::Style { background: Some(theme::TEXT_DIM.into()), ..container::Style::default() }).width(Length::Fill),
-            items,
-        ].spacing(15).into()
+            body,
+            export_row,
+            button(text("Close")).on_press(Message::CloseModal).style(theme::button_secondary).width(Length::Fill)
+        ].spacing(20)
     }
 
     fn view_footer<'a>(&self, state: &'a State) -> Element<'a, Message> {
-        let hours = state.session_focus_seconds / 3600;
-        let mins = (state.session_focus_seconds % 3600) / 60;
+        let focus_seconds = state.session_focus_seconds + state.running_focus_secs();
+        let hours = focus_seconds / 3600;
+        let mins = (focus_seconds % 3600) / 60;
 
         let stats = column![
             text("CURRENT SESSION").size(10).color(theme::TEXT_DIM).font(iced::Font { weight: iced::font::Weight::Bold, ..iced::Font::DEFAULT }),
@@ -656,6 +1837,11 @@ impl PomimiApp {
                     .on_press(Message::ToggleTheme)
                     .style(theme::button_secondary)
                     .width(40).height(40),
+                // Stats Icon
+                button(text("\u{e26b}").font(iced::Font::with_name("Material Symbols Outlined")).size(18)) // bar_chart
+                    .on_press(Message::OpenModal(Modal::Stats))
+                    .style(theme::button_secondary)
+                    .width(40).height(40),
                 // Settings Icon
                 button(text("\u{e8b8}").font(iced::Font::with_name("Material Symbols Outlined")).size(18)) // settings
                     .on_press(Message::OpenModal(Modal::Settings))
@@ -675,10 +1861,24 @@ impl PomimiApp {
 
     pub fn subscription(&self) -> Subscription<Message> {
         match self {
-            PomimiApp::Loaded(state) if state.timer.is_running => {
-                time::every(Duration::from_secs(1)).map(|_| Message::Tick)
+            PomimiApp::Loaded(state) => {
+                let window_events = iced::event::listen_with(|event, _status, id| match event {
+                    iced::Event::Window(window::Event::Resized(size)) => Some(Message::WindowResized(size)),
+                    iced::Event::Window(window::Event::Moved(position)) => Some(Message::WindowMoved(position)),
+                    iced::Event::Window(window::Event::CloseRequested) => Some(Message::CloseRequested(id)),
+                    _ => None,
+                });
+                if state.timer.is_running() {
+                    // Ticks only trigger a redraw; the countdown itself comes from the wall clock
+                    Subscription::batch(vec![
+                        window_events,
+                        time::every(Duration::from_millis(250)).map(|_| Message::Tick),
+                    ])
+                } else {
+                    window_events
+                }
             }
-            _ => Subscription::none(),
+            _ => window::close_requests().map(Message::CloseRequested),
         }
     }
 
@@ -691,3 +1891,26 @@ impl PomimiApp {
         }
     }
 }
+
+/// Runs a preference write in the background, reporting failures through
+/// [`Message::PreferenceSaved`].
+fn persist<F>(write: F) -> Task<Message>
+where
+    F: Future<Output = Result<(), sqlx::Error>> + Send + 'static,
+{
+    Task::perform(async move { write.await.map_err(|e| e.to_string()) }, Message::PreferenceSaved)
+}
+
+/// Runs a task write in the background and reloads the lists once it lands.
+fn task_operation<F>(write: F) -> Task<Message>
+where
+    F: Future<Output = Result<(), sqlx::Error>> + Send + 'static,
+{
+    Task::perform(
+        async move { write.await.map_err(|e| e.to_string()) },
+        |res| match res {
+            Ok(_) => Message::TaskOperationSuccess,
+            Err(e) => Message::TaskOperationFailed(e),
+        }
+    )
+}
@@ -0,0 +1,120 @@
+//! Canvas charts for the Stats modal.
+
+use chrono::{Datelike, NaiveDate};
+use iced::mouse;
+use iced::widget::canvas::{self, Geometry};
+use iced::widget::text::Alignment;
+use iced::{alignment, Color, Point, Rectangle, Renderer, Size, Theme};
+use crate::theme;
+
+const LABEL_SIZE: f32 = 10.0;
+const LABEL_HEIGHT: f32 = 14.0;
+
+fn label(content: String, position: Point, align_x: Alignment) -> canvas::Text {
+    canvas::Text {
+        content,
+        position,
+        color: theme::TEXT_DIM,
+        size: LABEL_SIZE.into(),
+        align_x,
+        align_y: alignment::Vertical::Top,
+        ..canvas::Text::default()
+    }
+}
+
+/// `1h 05m` or `25m`.
+pub fn format_minutes(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// One bar per day, today last.
+pub struct DayChart<'a> {
+    pub days: &'a [(NaiveDate, i64)],
+    pub color: Color,
+}
+
+impl<Message> canvas::Program<Message> for DayChart<'_> {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, _theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let max = self.days.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        let chart_top = LABEL_HEIGHT;
+        let chart_height = (bounds.height - 2.0 * LABEL_HEIGHT).max(0.0);
+        let slot = bounds.width / self.days.len().max(1) as f32;
+        let gap = (slot * 0.2).min(6.0);
+
+        frame.fill_rectangle(
+            Point::new(0.0, chart_top + chart_height),
+            Size::new(bounds.width, 1.0),
+            Color { a: 0.2, ..theme::TEXT_DIM },
+        );
+        if max > 0 {
+            frame.fill_text(label(format_minutes(max), Point::new(0.0, 0.0), Alignment::Left));
+        }
+
+        // Weekday initials fit a week; a month gets the day of the month every week
+        let every = if self.days.len() > 7 { 7 } else { 1 };
+        let last = self.days.len().saturating_sub(1);
+        for (i, (date, secs)) in self.days.iter().enumerate() {
+            let x = i as f32 * slot;
+            if *secs > 0 {
+                let height = (chart_height * *secs as f32 / max as f32).max(2.0);
+                frame.fill_rectangle(
+                    Point::new(x + gap / 2.0, chart_top + chart_height - height),
+                    Size::new(slot - gap, height),
+                    self.color,
+                );
+            }
+            if (last - i).is_multiple_of(every) {
+                let text = if every == 1 {
+                    date.format("%a").to_string().chars().take(1).collect()
+                } else {
+                    date.day().to_string()
+                };
+                frame.fill_text(label(text, Point::new(x + slot / 2.0, chart_top + chart_height + 3.0), Alignment::Center));
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Focus by the hour sessions started in, as two rows of twelve cells.
+pub struct HourHeatmap<'a> {
+    pub hours: &'a [i64; 24],
+    pub color: Color,
+}
+
+impl<Message> canvas::Program<Message> for HourHeatmap<'_> {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, _theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let max = self.hours.iter().copied().max().unwrap_or(0);
+        let cell = Size::new(bounds.width / 12.0, (bounds.height - 2.0 * LABEL_HEIGHT).max(0.0) / 2.0);
+
+        for (row, top) in [(0, 0.0), (1, cell.height + LABEL_HEIGHT)] {
+            for column in 0..12 {
+                let hour = row * 12 + column;
+                let x = column as f32 * cell.width;
+                let strength = if max > 0 { self.hours[hour] as f32 / max as f32 } else { 0.0 };
+                frame.fill_rectangle(
+                    Point::new(x + 1.0, top),
+                    Size::new(cell.width - 2.0, cell.height),
+                    Color { a: 0.08 + 0.92 * strength, ..self.color },
+                );
+                if hour.is_multiple_of(3) {
+                    frame.fill_text(label(hour.to_string(), Point::new(x + 1.0, top + cell.height + 2.0), Alignment::Left));
+                }
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
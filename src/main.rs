@@ -1,42 +1,47 @@
+mod alerts;
+mod args;
 mod cli;
+mod commands;
+mod export;
 mod model;
 mod gui;
+mod terminal;
 mod theme;
+mod timer;
+mod tui;
 
-use std::env;
+use args::{Args, Command};
+use clap::Parser;
 use gui::PomimiApp;
 
 fn main() -> iced::Result {
-    let args: Vec<String> = env::args().collect();
-    
-    let run_cli = if args.len() > 1 {
-        if args[1] == "--cli" {
-            true
-        } else {
-            let arg = &args[1];
-            // Simple heuristic to check if arg is time
-            if arg.ends_with('m') || arg.ends_with('s') || arg.parse::<u64>().is_ok() {
-                true
-            } else {
-                false
-            }
-        }
-    } else {
-        false
+    let args = Args::parse();
+
+    let result = match args.command.unwrap_or(Command::Gui) {
+        Command::Gui => return run_gui(),
+        Command::Timer(timer) => cli::run(timer),
+        Command::Tui => tui::run(),
+        command => commands::run(command),
     };
 
-    if run_cli {
-        cli::run();
-        Ok(())
-    } else {
-        iced::application(PomimiApp::new, PomimiApp::update, PomimiApp::view)
-            .title(PomimiApp::title)
-            .theme(PomimiApp::theme)
-            .subscription(PomimiApp::subscription)
-            .window(iced::window::Settings {
-                size: iced::Size::new(380.0, 800.0),
-                ..Default::default()
-            })
-            .run()
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
     }
+    Ok(())
+}
+
+fn run_gui() -> iced::Result {
+    iced::application(PomimiApp::new, PomimiApp::update, PomimiApp::view)
+        .title(PomimiApp::title)
+        .theme(PomimiApp::theme)
+        .subscription(PomimiApp::subscription)
+        .window(iced::window::Settings {
+            size: iced::Size::new(380.0, 800.0),
+            // Closing goes through `Message::CloseRequested` so an unfinished
+            // focus block can be stored first
+            exit_on_close_request: false,
+            ..Default::default()
+        })
+        .run()
 }
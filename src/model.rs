@@ -1,12 +1,376 @@
-use sqlx::sqlite::SqlitePool;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool};
+use chrono::NaiveDate;
 use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use chrono;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+mod backup;
+mod export_rows;
+mod import;
+mod migrations;
+mod projects;
+mod stats;
+mod subtasks;
+
+pub use backup::{check_backup, restore};
+pub use export_rows::{DateRange, SessionRecord, TaskRecord};
+pub use import::{parse_tasks, ImportFormat};
+pub use projects::{parse_tags, LabelFilter, Project, PROJECT_COLORS};
+pub use stats::{EstimateWeek, FocusStats};
+pub use subtasks::Subtask;
 
 #[derive(Clone, Debug, sqlx::FromRow)]
 pub struct Task {
     pub id: i64,
     pub text: String,
+    pub completed_at: Option<i64>,
+    pub notes: Option<String>,
+    pub due_date: Option<NaiveDate>,
+    /// 1 (highest) to [`LOWEST_PRIORITY`]; `None` for no priority.
+    pub priority: Option<u8>,
+    /// Pomodoros the task was expected to take.
+    pub estimate: Option<u32>,
+    /// Completed focus blocks attributed to the task; read-only.
+    pub pomodoros: u32,
+    /// Checklist steps, in order; saved through the subtask methods.
+    #[sqlx(skip)]
+    pub subtasks: Vec<Subtask>,
+    pub project_id: Option<i64>,
+    /// Name of the project, joined in; read-only.
+    pub project: Option<String>,
+    /// The project's color as `#RRGGBB`; see [`Task::project_rgb`].
+    pub project_color: Option<String>,
+    /// Saved through [`Database::set_task_tags`].
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+}
+
+/// Priorities run from P1 down to P3.
+pub const LOWEST_PRIORITY: u8 = 3;
+
+/// Columns loaded into a [`Task`], selected from [`TASK_TABLES`].
+const TASK_COLUMNS: &str = "tasks.id, tasks.text, tasks.completed_at, tasks.notes, tasks.due_date, tasks.priority, tasks.estimate,
+    (SELECT COUNT(*) FROM sessions WHERE sessions.task_id = tasks.id AND sessions.completed) AS pomodoros,
+    tasks.project_id, projects.name AS project, projects.color AS project_color";
+
+/// Tasks with their project joined in.
+const TASK_TABLES: &str = "tasks LEFT JOIN projects ON projects.id = tasks.project_id";
+
+/// Sort key of a priority bucket: P1 first, tasks without a priority last.
+pub fn priority_bucket(priority: Option<u8>) -> u8 {
+    priority.unwrap_or(LOWEST_PRIORITY + 1)
+}
+
+/// `ORDER BY` for the open list, shared by everything that reads or reorders it.
+fn open_order(group_by_priority: bool) -> String {
+    if group_by_priority {
+        format!("COALESCE(tasks.priority, {}), tasks.position, tasks.id", LOWEST_PRIORITY + 1)
+    } else {
+        "tasks.position, tasks.id".to_string()
+    }
+}
+
+/// Which slice of the task list to load.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TaskFilter {
+    Open,
+    /// Done but not yet archived.
+    Completed,
+    Archived,
+}
+
+impl TaskFilter {
+    fn where_clause(&self) -> &'static str {
+        match self {
+            TaskFilter::Open => "completed_at IS NULL",
+            TaskFilter::Completed => "completed_at IS NOT NULL AND archived_at IS NULL",
+            TaskFilter::Archived => "archived_at IS NOT NULL",
+        }
+    }
+}
+
+/// Shortest phase, in seconds, a stored profile can hold.
+pub const MIN_PHASE_SECS: u64 = 60;
+
+/// Smallest full window, in logical pixels, the layout still fits in.
+pub const MIN_WINDOW_SIZE: (f32, f32) = (300.0, 400.0);
+
+/// A named set of phase lengths, e.g. the classic 25/5 Pomodoro.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimerProfile {
+    pub name: String,
+    pub focus_secs: u64,
+    pub short_break_secs: u64,
+    pub long_break_secs: u64,
+    /// Number of focus blocks before a long break replaces the short one.
+    pub cycles_before_long_break: usize,
+}
+
+impl TimerProfile {
+    pub fn defaults() -> Vec<TimerProfile> {
+        vec![
+            TimerProfile {
+                name: "Classic".to_string(),
+                focus_secs: 25 * 60,
+                short_break_secs: 5 * 60,
+                long_break_secs: 30 * 60,
+                cycles_before_long_break: 4,
+            },
+            TimerProfile {
+                name: "Long".to_string(),
+                focus_secs: 50 * 60,
+                short_break_secs: 10 * 60,
+                long_break_secs: 30 * 60,
+                cycles_before_long_break: 4,
+            },
+        ]
+    }
+
+    /// Raises phases shorter than [`MIN_PHASE_SECS`] to it, so a hand-edited
+    /// profile of zero-length phases cannot spin the timer.
+    fn clamped(mut self) -> Self {
+        self.focus_secs = self.focus_secs.max(MIN_PHASE_SECS);
+        self.short_break_secs = self.short_break_secs.max(MIN_PHASE_SECS);
+        self.long_break_secs = self.long_break_secs.max(MIN_PHASE_SECS);
+        self.cycles_before_long_break = self.cycles_before_long_break.max(1);
+        self
+    }
+
+    /// Short label such as "25/5" used by the strategy buttons and the CLI menu.
+    pub fn label(&self) -> String {
+        format!("{}/{}", self.focus_secs / 60, self.short_break_secs / 60)
+    }
+}
+
+/// A focus block as it gets written to the `sessions` table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FocusSession {
+    pub task_id: Option<i64>,
+    pub planned_seconds: i64,
+    pub actual_seconds: i64,
+    /// Unix timestamps (seconds).
+    pub started_at: i64,
+    pub ended_at: i64,
+    /// `false` when the block was cut short (profile switch, app closed...).
+    pub completed: bool,
+}
+
+const PROFILES_KEY: &str = "timer_profiles";
+const ACCENT_COLOR_KEY: &str = "accent_color";
+const THEME_MODE_KEY: &str = "theme_mode";
+const SELECTED_PROFILE_KEY: &str = "selected_profile";
+const WINDOW_SIZE_KEY: &str = "window_size";
+const MINI_POSITION_KEY: &str = "mini_position";
+const ALERT_SOUND_KEY: &str = "alert_sound";
+const ALERT_VOLUME_KEY: &str = "alert_volume";
+const SILENT_KEY: &str = "silent";
+const DAY_START_HOUR_KEY: &str = "day_start_hour";
+const GROUP_BY_PRIORITY_KEY: &str = "group_by_priority";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThemeMode {
+    Dark,
+    Light,
+}
+
+impl ThemeMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ThemeMode::Dark => "dark",
+            ThemeMode::Light => "light",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "dark" => Some(ThemeMode::Dark),
+            "light" => Some(ThemeMode::Light),
+            _ => None,
+        }
+    }
+}
+
+/// Sound played when a phase ends.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlertSound {
+    Chime,
+    Bell,
+    Wood,
+    /// A WAV file chosen by the user.
+    Custom(String),
+}
+
+impl AlertSound {
+    pub const BUNDLED: [AlertSound; 3] = [AlertSound::Chime, AlertSound::Bell, AlertSound::Wood];
+
+    pub fn name(&self) -> &str {
+        match self {
+            AlertSound::Chime => "chime",
+            AlertSound::Bell => "bell",
+            AlertSound::Wood => "wood",
+            AlertSound::Custom(path) => path,
+        }
+    }
+
+    /// Bundled sounds are stored by name, custom ones as `file:<path>`.
+    fn to_value(&self) -> String {
+        match self {
+            AlertSound::Custom(path) => format!("file:{}", path),
+            bundled => bundled.name().to_string(),
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "chime" => Some(AlertSound::Chime),
+            "bell" => Some(AlertSound::Bell),
+            "wood" => Some(AlertSound::Wood),
+            _ => value.strip_prefix("file:").map(|path| AlertSound::Custom(path.to_string())),
+        }
+    }
+}
+
+/// Typed view over the `preferences` table. Missing or malformed values fall
+/// back to the defaults so a damaged row never keeps the app from starting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Accent color as 8-bit RGB.
+    pub accent_color: [u8; 3],
+    pub theme_mode: ThemeMode,
+    /// Name of the selected [`TimerProfile`].
+    pub selected_profile: Option<String>,
+    /// Size of the full window, in logical pixels.
+    pub window_size: (f32, f32),
+    /// Last position of the mini window, if it was ever moved.
+    pub mini_position: Option<(f32, f32)>,
+    pub alert_sound: AlertSound,
+    /// Alert volume in percent, 0-100.
+    pub alert_volume: u8,
+    /// Suppresses sounds and notifications altogether.
+    pub silent: bool,
+    /// Local hour, 0-23, at which a new day starts for focus totals.
+    pub day_start_hour: u8,
+    /// Sorts open tasks into P1-P3 buckets, keeping the manual order within each.
+    pub group_by_priority: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            accent_color: [0xFB, 0x2C, 0x2D],
+            theme_mode: ThemeMode::Dark,
+            selected_profile: None,
+            window_size: (380.0, 800.0),
+            mini_position: None,
+            alert_sound: AlertSound::Chime,
+            alert_volume: 80,
+            silent: false,
+            day_start_hour: 0,
+            group_by_priority: false,
+        }
+    }
+}
+
+/// Setting names understood by [`Settings::get`] and [`Database::set_setting`].
+pub const SETTING_KEYS: &[&str] = &[
+    ACCENT_COLOR_KEY,
+    THEME_MODE_KEY,
+    SELECTED_PROFILE_KEY,
+    WINDOW_SIZE_KEY,
+    MINI_POSITION_KEY,
+    ALERT_SOUND_KEY,
+    ALERT_VOLUME_KEY,
+    SILENT_KEY,
+    DAY_START_HOUR_KEY,
+    GROUP_BY_PRIORITY_KEY,
+];
+
+#[derive(Debug)]
+pub enum SettingError {
+    UnknownKey(String),
+    InvalidValue { key: String, value: String, expected: &'static str },
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for SettingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingError::UnknownKey(key) => {
+                write!(f, "unknown setting '{}', expected one of: {}", key, SETTING_KEYS.join(", "))
+            }
+            SettingError::InvalidValue { key, value, expected } => {
+                write!(f, "invalid value '{}' for {}, expected {}", value, key, expected)
+            }
+            SettingError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for SettingError {
+    fn from(e: sqlx::Error) -> Self {
+        SettingError::Database(e)
+    }
+}
+
+impl Settings {
+    /// Renders one setting in the same text form [`Database::set_setting`] accepts.
+    pub fn get(&self, key: &str) -> Result<String, SettingError> {
+        let value = match key {
+            ACCENT_COLOR_KEY => format_hex_color(self.accent_color),
+            THEME_MODE_KEY => self.theme_mode.as_str().to_string(),
+            SELECTED_PROFILE_KEY => self.selected_profile.clone().unwrap_or_default(),
+            WINDOW_SIZE_KEY => format!("{}x{}", self.window_size.0, self.window_size.1),
+            MINI_POSITION_KEY => self.mini_position.map(|(x, y)| format!("{},{}", x, y)).unwrap_or_default(),
+            ALERT_SOUND_KEY => self.alert_sound.to_value(),
+            ALERT_VOLUME_KEY => self.alert_volume.to_string(),
+            SILENT_KEY => self.silent.to_string(),
+            DAY_START_HOUR_KEY => self.day_start_hour.to_string(),
+            GROUP_BY_PRIORITY_KEY => self.group_by_priority.to_string(),
+            _ => return Err(SettingError::UnknownKey(key.to_string())),
+        };
+        Ok(value)
+    }
+}
+
+pub fn format_hex_color(rgb: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
+
+/// Parses `#RRGGBB`.
+pub fn parse_hex_color(value: &str) -> Option<[u8; 3]> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn parse_day_start_hour(value: &str) -> Option<u8> {
+    value.parse::<u8>().ok().filter(|hour| *hour < 24)
+}
+
+/// Two finite numbers; `NaN` and `inf` parse as floats but are no use as pixels.
+fn parse_pair(value: &str, separator: char) -> Option<(f32, f32)> {
+    let (a, b) = value.split_once(separator)?;
+    let (a, b): (f32, f32) = (a.trim().parse().ok()?, b.trim().parse().ok()?);
+    (a.is_finite() && b.is_finite()).then_some((a, b))
+}
+
+/// Where pomimi keeps its database and backups.
+pub fn data_dir() -> PathBuf {
+    ProjectDirs::from("com", "pomimi", "pomimi").unwrap().data_dir().to_path_buf()
+}
+
+pub fn database_path() -> PathBuf {
+    data_dir().join("pomimi.db")
+}
+
+pub fn backups_dir() -> PathBuf {
+    data_dir().join("backups")
 }
 
 #[derive(Clone, Debug)]
@@ -16,12 +380,11 @@ pub struct Database {
 
 impl Database {
     pub async fn new() -> Result<Self, sqlx::Error> {
-        let proj_dirs = ProjectDirs::from("com", "pomimi", "pomimi").unwrap();
-        let data_dir = proj_dirs.data_dir();
+        let data_dir = data_dir();
         if !data_dir.exists() {
-            let _ = fs::create_dir_all(data_dir);
+            let _ = fs::create_dir_all(&data_dir);
         }
-        let db_path = data_dir.join("pomimi.db");
+        let db_path = database_path();
         // Ensure the file exists so sqlite can open it
         if !db_path.exists() {
             fs::File::create(&db_path).expect("Failed to create db file");
@@ -29,48 +392,77 @@ impl Database {
 
         let db_url = format!("sqlite://{}", db_path.to_string_lossy());
 
-        let pool = SqlitePool::connect(&db_url).await?;
-
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS tasks (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                text TEXT NOT NULL,
-                completed BOOLEAN NOT NULL DEFAULT 0,
-                created_at INTEGER NOT NULL
-            )"
-        ).execute(&pool).await?;
-
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS preferences (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )"
-        ).execute(&pool).await?;
-
-        sqlx::query(
-             "CREATE TABLE IF NOT EXISTS sessions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                start_time INTEGER NOT NULL,
-                duration_seconds INTEGER NOT NULL
-             )"
-        ).execute(&pool).await?;
+        let db = Self::open(&db_url).await?;
+        // A failed backup is no reason to keep anyone from their tasks
+        if let Err(e) = db.auto_backup(&backups_dir(), chrono::Local::now().date_naive()).await {
+            eprintln!("automatic backup failed: {}", e);
+        }
+        Ok(db)
+    }
 
+    /// Connects to `db_url` and upgrades it to the latest schema. The
+    /// database is kept in WAL mode, which also lets a restore tell whether
+    /// pomimi still has it open.
+    pub async fn open(db_url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(db_url)?.journal_mode(SqliteJournalMode::Wal);
+        let pool = SqlitePool::connect_with(options).await?;
+        migrations::run(&pool).await?;
         Ok(Self { pool })
     }
 
+    /// Waits for pending writes and closes every connection.
+    pub async fn close(&self) {
+        self.pool.close().await;
+        // A connection handed back while closing can still end up idle, and
+        // would keep the file open; closing again shuts it too
+        if self.pool.num_idle() > 0 {
+            self.pool.close().await;
+        }
+    }
+
+    /// Open tasks only; see [`Database::get_tasks_with`] for done or archived ones.
     pub async fn get_tasks(&self) -> Result<Vec<Task>, sqlx::Error> {
-        let tasks = sqlx::query_as::<_, Task>(
-            "SELECT id, text FROM tasks ORDER BY created_at DESC"
-        )
+        self.get_tasks_with(TaskFilter::Open).await
+    }
+
+    /// Open tasks come in the order shown everywhere: manual order, grouped
+    /// by priority when [`Settings::group_by_priority`] is on.
+    pub async fn get_tasks_with(&self, filter: TaskFilter) -> Result<Vec<Task>, sqlx::Error> {
+        let order = match filter {
+            TaskFilter::Open => open_order(self.group_by_priority().await?),
+            TaskFilter::Completed | TaskFilter::Archived => "completed_at DESC".to_string(),
+        };
+        let mut tasks = sqlx::query_as::<_, Task>(&format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY {}",
+            TASK_COLUMNS,
+            TASK_TABLES,
+            filter.where_clause(),
+            order
+        ))
         .fetch_all(&self.pool)
         .await?;
+        self.load_subtasks(&mut tasks).await?;
+        self.load_tags(&mut tasks).await?;
         Ok(tasks)
     }
 
+    pub async fn get_task(&self, id: i64) -> Result<Option<Task>, sqlx::Error> {
+        let task = sqlx::query_as::<_, Task>(&format!("SELECT {} FROM {} WHERE tasks.id = ?", TASK_COLUMNS, TASK_TABLES))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        let mut tasks: Vec<Task> = task.into_iter().collect();
+        self.load_subtasks(&mut tasks).await?;
+        self.load_tags(&mut tasks).await?;
+        Ok(tasks.pop())
+    }
+
+    /// Adds a task at the top of the open list.
     pub async fn add_task(&self, text: &str) -> Result<(), sqlx::Error> {
         let now = chrono::Utc::now().timestamp();
         sqlx::query(
-            "INSERT INTO tasks (text, completed, created_at) VALUES (?, 0, ?)"
+            "INSERT INTO tasks (text, completed, created_at, position)
+             VALUES (?, 0, ?, (SELECT COALESCE(MIN(position), 0) - 1 FROM tasks))"
         )
         .bind(text)
         .bind(now)
@@ -79,6 +471,90 @@ impl Database {
         Ok(())
     }
 
+    /// Saves the text, notes, due date, priority and estimate of `task`.
+    pub async fn update_task(&self, task: &Task) -> Result<(), sqlx::Error> {
+        let result = sqlx::query("UPDATE tasks SET text = ?, notes = ?, due_date = ?, priority = ?, estimate = ? WHERE id = ?")
+            .bind(&task.text)
+            .bind(&task.notes)
+            .bind(task.due_date)
+            .bind(task.priority)
+            .bind(task.estimate)
+            .bind(task.id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn complete_task(&self, id: i64) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query("UPDATE tasks SET completed = 1, completed_at = ? WHERE id = ? AND completed_at IS NULL")
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves a done or archived task back to the open list.
+    pub async fn reopen_task(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE tasks SET completed = 0, completed_at = NULL, archived_at = NULL WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Archives every completed task, clearing the "Completed" list.
+    pub async fn archive_completed(&self) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query("UPDATE tasks SET archived_at = ? WHERE completed_at IS NOT NULL AND archived_at IS NULL")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves an open task to `index` (0-based) in the open list as shown,
+    /// shifting the others down. Indexes past the end move it to the bottom.
+    /// When the list is grouped by priority the task stays within its own
+    /// bucket, so the index is clamped to the bucket's range.
+    pub async fn move_task(&self, id: i64, index: usize) -> Result<(), sqlx::Error> {
+        let grouped = self.group_by_priority().await?;
+        let mut tx = self.pool.begin().await?;
+        let mut tasks: Vec<(i64, Option<u8>)> = sqlx::query_as(&format!(
+            "SELECT id, priority FROM tasks WHERE {} ORDER BY {}",
+            TaskFilter::Open.where_clause(),
+            open_order(grouped)
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let Some(from) = tasks.iter().position(|&(task, _)| task == id) else {
+            return Err(sqlx::Error::RowNotFound);
+        };
+        let (_, priority) = tasks.remove(from);
+        let mut index = index.min(tasks.len());
+        if grouped {
+            let bucket = priority_bucket(priority);
+            let start = tasks.iter().filter(|(_, p)| priority_bucket(*p) < bucket).count();
+            let end = start + tasks.iter().filter(|(_, p)| priority_bucket(*p) == bucket).count();
+            index = index.clamp(start, end);
+        }
+        tasks.insert(index, (id, priority));
+
+        for (position, (task, _)) in tasks.iter().enumerate() {
+            sqlx::query("UPDATE tasks SET position = ? WHERE id = ?")
+                .bind(position as i64)
+                .bind(task)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
     pub async fn delete_task(&self, id: i64) -> Result<(), sqlx::Error> {
         sqlx::query("DELETE FROM tasks WHERE id = ?")
             .bind(id)
@@ -87,35 +563,447 @@ impl Database {
         Ok(())
     }
 
+    // Preferences
+    pub async fn get_preference(&self, key: &str) -> Result<Option<String>, sqlx::Error> {
+        let value: Option<String> = sqlx::query_scalar(
+            "SELECT value FROM preferences WHERE key = ?"
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(value)
+    }
+
+    pub async fn set_preference(&self, key: &str, value: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO preferences (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns the stored timer profiles, falling back to the built-in ones
+    /// when nothing (or something unreadable) has been saved yet.
+    pub async fn get_profiles(&self) -> Result<Vec<TimerProfile>, sqlx::Error> {
+        let profiles = self.get_preference(PROFILES_KEY).await?
+            .and_then(|json| serde_json::from_str::<Vec<TimerProfile>>(&json).ok())
+            .filter(|profiles| !profiles.is_empty())
+            .map(|profiles| profiles.into_iter().map(TimerProfile::clamped).collect())
+            .unwrap_or_else(TimerProfile::defaults);
+        Ok(profiles)
+    }
+
+    pub async fn save_profiles(&self, profiles: &[TimerProfile]) -> Result<(), sqlx::Error> {
+        let json = serde_json::to_string(profiles).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        self.set_preference(PROFILES_KEY, &json).await
+    }
+
+    pub async fn load_settings(&self) -> Result<Settings, sqlx::Error> {
+        let defaults = Settings::default();
+        Ok(Settings {
+            accent_color: self.get_preference(ACCENT_COLOR_KEY).await?
+                .and_then(|v| parse_hex_color(&v))
+                .unwrap_or(defaults.accent_color),
+            theme_mode: self.get_preference(THEME_MODE_KEY).await?
+                .and_then(|v| ThemeMode::parse(&v))
+                .unwrap_or(defaults.theme_mode),
+            selected_profile: self.get_preference(SELECTED_PROFILE_KEY).await?,
+            window_size: self.get_preference(WINDOW_SIZE_KEY).await?
+                .and_then(|v| parse_pair(&v, 'x'))
+                .map(|(width, height)| (width.max(MIN_WINDOW_SIZE.0), height.max(MIN_WINDOW_SIZE.1)))
+                .unwrap_or(defaults.window_size),
+            mini_position: self.get_preference(MINI_POSITION_KEY).await?
+                .and_then(|v| parse_pair(&v, ',')),
+            alert_sound: self.get_preference(ALERT_SOUND_KEY).await?
+                .and_then(|v| AlertSound::parse(&v))
+                .unwrap_or(defaults.alert_sound),
+            alert_volume: self.get_preference(ALERT_VOLUME_KEY).await?
+                .and_then(|v| v.parse::<u8>().ok())
+                .map(|v| v.min(100))
+                .unwrap_or(defaults.alert_volume),
+            silent: self.get_preference(SILENT_KEY).await?
+                .map(|v| v == "true")
+                .unwrap_or(defaults.silent),
+            day_start_hour: self.day_start_hour().await?,
+            group_by_priority: self.group_by_priority().await?,
+        })
+    }
+
+    /// Validates and stores a setting given as text, e.g. from `pomimi config set`.
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<(), SettingError> {
+        let invalid = |expected| SettingError::InvalidValue { key: key.to_string(), value: value.to_string(), expected };
+        match key {
+            ACCENT_COLOR_KEY => {
+                let rgb = parse_hex_color(value).ok_or_else(|| invalid("a color like #FB2C2D"))?;
+                self.set_accent_color(rgb).await?
+            }
+            THEME_MODE_KEY => {
+                let mode = ThemeMode::parse(value).ok_or_else(|| invalid("dark or light"))?;
+                self.set_theme_mode(mode).await?
+            }
+            SELECTED_PROFILE_KEY => {
+                let profiles = self.get_profiles().await?;
+                if !profiles.iter().any(|p| p.name == value) {
+                    return Err(invalid("the name of a timer profile"));
+                }
+                self.set_selected_profile(value).await?
+            }
+            WINDOW_SIZE_KEY => {
+                let (width, height) = parse_pair(value, 'x')
+                    .filter(|&(width, height)| width >= MIN_WINDOW_SIZE.0 && height >= MIN_WINDOW_SIZE.1)
+                    .ok_or_else(|| invalid("WIDTHxHEIGHT of at least 300x400, e.g. 380x800"))?;
+                self.set_window_size(width, height).await?
+            }
+            MINI_POSITION_KEY => {
+                let (x, y) = parse_pair(value, ',').ok_or_else(|| invalid("X,Y, e.g. 1630,20"))?;
+                self.set_mini_position(x, y).await?
+            }
+            ALERT_SOUND_KEY => {
+                let sound = AlertSound::parse(value).ok_or_else(|| invalid("chime, bell, wood or file:<path>"))?;
+                self.set_alert_sound(&sound).await?
+            }
+            ALERT_VOLUME_KEY => {
+                let volume = value.parse::<u8>().ok().filter(|v| *v <= 100).ok_or_else(|| invalid("a number from 0 to 100"))?;
+                self.set_alert_volume(volume).await?
+            }
+            SILENT_KEY => {
+                let silent = value.parse::<bool>().map_err(|_| invalid("true or false"))?;
+                self.set_silent(silent).await?
+            }
+            DAY_START_HOUR_KEY => {
+                let hour = parse_day_start_hour(value).ok_or_else(|| invalid("an hour from 0 to 23"))?;
+                self.set_day_start_hour(hour).await?
+            }
+            GROUP_BY_PRIORITY_KEY => {
+                let grouped = value.parse::<bool>().map_err(|_| invalid("true or false"))?;
+                self.set_group_by_priority(grouped).await?
+            }
+            _ => return Err(SettingError::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    pub async fn set_accent_color(&self, rgb: [u8; 3]) -> Result<(), sqlx::Error> {
+        self.set_preference(ACCENT_COLOR_KEY, &format_hex_color(rgb)).await
+    }
+
+    pub async fn set_theme_mode(&self, mode: ThemeMode) -> Result<(), sqlx::Error> {
+        self.set_preference(THEME_MODE_KEY, mode.as_str()).await
+    }
+
+    pub async fn set_selected_profile(&self, name: &str) -> Result<(), sqlx::Error> {
+        self.set_preference(SELECTED_PROFILE_KEY, name).await
+    }
+
+    pub async fn set_window_size(&self, width: f32, height: f32) -> Result<(), sqlx::Error> {
+        self.set_preference(WINDOW_SIZE_KEY, &format!("{}x{}", width, height)).await
+    }
+
+    pub async fn set_alert_sound(&self, sound: &AlertSound) -> Result<(), sqlx::Error> {
+        self.set_preference(ALERT_SOUND_KEY, &sound.to_value()).await
+    }
+
+    pub async fn set_alert_volume(&self, volume: u8) -> Result<(), sqlx::Error> {
+        self.set_preference(ALERT_VOLUME_KEY, &volume.min(100).to_string()).await
+    }
+
+    pub async fn set_silent(&self, silent: bool) -> Result<(), sqlx::Error> {
+        self.set_preference(SILENT_KEY, if silent { "true" } else { "false" }).await
+    }
+
+    pub async fn day_start_hour(&self) -> Result<u8, sqlx::Error> {
+        Ok(self.get_preference(DAY_START_HOUR_KEY).await?
+            .and_then(|v| parse_day_start_hour(&v))
+            .unwrap_or(Settings::default().day_start_hour))
+    }
+
+    pub async fn set_day_start_hour(&self, hour: u8) -> Result<(), sqlx::Error> {
+        self.set_preference(DAY_START_HOUR_KEY, &(hour % 24).to_string()).await
+    }
+
+    pub async fn group_by_priority(&self) -> Result<bool, sqlx::Error> {
+        Ok(self.get_preference(GROUP_BY_PRIORITY_KEY).await?
+            .map(|v| v == "true")
+            .unwrap_or(Settings::default().group_by_priority))
+    }
+
+    pub async fn set_group_by_priority(&self, grouped: bool) -> Result<(), sqlx::Error> {
+        self.set_preference(GROUP_BY_PRIORITY_KEY, if grouped { "true" } else { "false" }).await
+    }
+
+    pub async fn set_mini_position(&self, x: f32, y: f32) -> Result<(), sqlx::Error> {
+        self.set_preference(MINI_POSITION_KEY, &format!("{},{}", x, y)).await
+    }
+
     // Sessions
-    pub async fn add_session(&self, duration_seconds: i64) -> Result<(), sqlx::Error> {
-        let now = chrono::Utc::now().timestamp();
+    pub async fn add_session(&self, session: &FocusSession) -> Result<(), sqlx::Error> {
         sqlx::query(
-            "INSERT INTO sessions (start_time, duration_seconds) VALUES (?, ?)"
+            "INSERT INTO sessions (start_time, end_time, duration_seconds, planned_seconds, task_id, completed)
+             VALUES (?, ?, ?, ?, ?, ?)"
         )
-        .bind(now)
-        .bind(duration_seconds)
+        .bind(session.started_at)
+        .bind(session.ended_at)
+        .bind(session.actual_seconds)
+        .bind(session.planned_seconds)
+        .bind(session.task_id)
+        .bind(session.completed)
         .execute(&self.pool)
         .await?;
         Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    pub(super) async fn memory_db() -> Database {
+        // One connection, otherwise every connection gets its own in-memory database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        migrations::run(&pool).await.unwrap();
+        Database { pool }
+    }
+
+    async fn open_texts(db: &Database) -> Vec<String> {
+        db.get_tasks().await.unwrap().into_iter().map(|t| t.text).collect()
+    }
+
+    #[tokio::test]
+    async fn clamps_stored_profiles() {
+        let db = memory_db().await;
+        let json = r#"[{"name":"Broken","focus_secs":0,"short_break_secs":0,"long_break_secs":0,"cycles_before_long_break":0}]"#;
+        db.set_preference(PROFILES_KEY, json).await.unwrap();
+        let profile = db.get_profiles().await.unwrap().remove(0);
+        assert_eq!(
+            (profile.focus_secs, profile.short_break_secs, profile.long_break_secs, profile.cycles_before_long_break),
+            (MIN_PHASE_SECS, MIN_PHASE_SECS, MIN_PHASE_SECS, 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn settings_round_trip() {
+        let db = memory_db().await;
+        assert_eq!(db.load_settings().await.unwrap(), Settings::default());
+
+        db.set_accent_color([0x12, 0xAB, 0xEF]).await.unwrap();
+        db.set_theme_mode(ThemeMode::Light).await.unwrap();
+        db.set_selected_profile("Long").await.unwrap();
+        db.set_window_size(420.0, 900.5).await.unwrap();
+        db.set_mini_position(1630.0, 20.0).await.unwrap();
+        db.set_alert_sound(&AlertSound::Custom("/tmp/ding.wav".to_string())).await.unwrap();
+        db.set_alert_volume(35).await.unwrap();
+        db.set_silent(true).await.unwrap();
+        db.set_day_start_hour(4).await.unwrap();
+        db.set_group_by_priority(true).await.unwrap();
+
+        assert_eq!(db.load_settings().await.unwrap(), Settings {
+            accent_color: [0x12, 0xAB, 0xEF],
+            theme_mode: ThemeMode::Light,
+            selected_profile: Some("Long".to_string()),
+            window_size: (420.0, 900.5),
+            mini_position: Some((1630.0, 20.0)),
+            alert_sound: AlertSound::Custom("/tmp/ding.wav".to_string()),
+            alert_volume: 35,
+            silent: true,
+            day_start_hour: 4,
+            group_by_priority: true,
+        });
+    }
+
+    #[tokio::test]
+    async fn malformed_settings_fall_back_to_defaults() {
+        let db = memory_db().await;
+        for (key, value) in [
+            (ACCENT_COLOR_KEY, "red"),
+            (THEME_MODE_KEY, "sepia"),
+            (WINDOW_SIZE_KEY, "wide"),
+            (MINI_POSITION_KEY, "NaN,20"),
+            (ALERT_SOUND_KEY, "gong"),
+            (ALERT_VOLUME_KEY, "loud"),
+            (DAY_START_HOUR_KEY, "25"),
+        ] {
+            db.set_preference(key, value).await.unwrap();
+        }
+        assert_eq!(db.load_settings().await.unwrap(), Settings::default());
+
+        // Out-of-range volumes and window sizes are capped rather than dropped
+        db.set_preference(ALERT_VOLUME_KEY, "250").await.unwrap();
+        db.set_preference(WINDOW_SIZE_KEY, "-5x0").await.unwrap();
+        let settings = db.load_settings().await.unwrap();
+        assert_eq!((settings.alert_volume, settings.window_size), (100, MIN_WINDOW_SIZE));
+        db.set_preference(WINDOW_SIZE_KEY, "infx800").await.unwrap();
+        assert_eq!(db.load_settings().await.unwrap().window_size, Settings::default().window_size);
+
+        for (key, value) in [
+            (WINDOW_SIZE_KEY, "NaNx800"),
+            (WINDOW_SIZE_KEY, "0x0"),
+            (WINDOW_SIZE_KEY, "380x-800"),
+            (MINI_POSITION_KEY, "inf,20"),
+        ] {
+            assert!(matches!(db.set_setting(key, value).await, Err(SettingError::InvalidValue { .. })), "{}={}", key, value);
+        }
+        db.set_setting(MINI_POSITION_KEY, "-1200,20").await.unwrap();
+        assert_eq!(db.load_settings().await.unwrap().mini_position, Some((-1200.0, 20.0)));
+
+        assert!(matches!(db.set_setting(THEME_MODE_KEY, "sepia").await, Err(SettingError::InvalidValue { .. })));
+        assert!(matches!(db.set_setting("colour", "red").await, Err(SettingError::UnknownKey(_))));
+    }
+
+    #[tokio::test]
+    async fn stores_finished_and_interrupted_focus_blocks() {
+        use crate::timer::Timer;
+        use std::time::{Duration, SystemTime};
 
-    pub async fn get_today_focus_time(&self) -> Result<i64, sqlx::Error> {
-        // Start of today
-        let today = chrono::Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+        let db = memory_db().await;
+        db.add_task("report").await.unwrap();
+        let task_id = db.get_tasks().await.unwrap()[0].id;
+        let at = |secs: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + secs);
 
-        // Use query_scalar to get a single value (Option<i64> because SUM can be NULL)
-        let result: Option<i64> = sqlx::query_scalar(
-            "SELECT SUM(duration_seconds) FROM sessions WHERE start_time >= ?"
+        // A full block, then one reset (or closed) ten minutes in
+        let mut timer = Timer::new(TimerProfile::defaults().remove(0)).with_task(Some(task_id));
+        timer.start(at(0));
+        let finished = timer.tick(at(25 * 60));
+        timer.skip(at(26 * 60));
+        let interrupted = timer.reset(at(40 * 60));
+        for event in finished.iter().chain(&interrupted) {
+            if let Some(session) = event.focus_session() {
+                db.add_session(&session).await.unwrap();
+            }
+        }
+
+        let rows: Vec<(i64, i64, i64, i64, Option<i64>, bool)> = sqlx::query_as(
+            "SELECT start_time, end_time, duration_seconds, planned_seconds, task_id, completed FROM sessions ORDER BY id"
         )
-        .bind(today)
-        .fetch_one(&self.pool)
+        .fetch_all(&db.pool)
         .await
-        .unwrap_or(None); // fetch_one might fail if table empty? No, aggregation always returns a row.
+        .unwrap();
+        assert_eq!(rows, [
+            (1_000_000, 1_000_000 + 25 * 60, 25 * 60, 25 * 60, Some(task_id), true),
+            (1_000_000 + 26 * 60, 1_000_000 + 40 * 60, 14 * 60, 25 * 60, Some(task_id), false),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn new_tasks_go_on_top() {
+        let db = memory_db().await;
+        for text in ["a", "b", "c"] {
+            db.add_task(text).await.unwrap();
+        }
+        assert_eq!(open_texts(&db).await, ["c", "b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn moves_tasks_within_the_open_list() {
+        let db = memory_db().await;
+        for text in ["a", "b", "c", "d"] {
+            db.add_task(text).await.unwrap();
+        }
+        let id_of = |tasks: &[Task], text: &str| tasks.iter().find(|t| t.text == text).unwrap().id;
+        let tasks = db.get_tasks().await.unwrap();
+
+        db.move_task(id_of(&tasks, "a"), 0).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["a", "d", "c", "b"]);
+
+        db.move_task(id_of(&tasks, "d"), 99).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["a", "c", "b", "d"]);
+
+        db.complete_task(id_of(&tasks, "c")).await.unwrap();
+        db.move_task(id_of(&tasks, "b"), 0).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["b", "a", "d"]);
+
+        assert!(matches!(db.move_task(id_of(&tasks, "c"), 0).await, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn groups_open_tasks_by_priority() {
+        let db = memory_db().await;
+        for (text, priority) in [("d", None), ("c", Some(2)), ("b", None), ("a", Some(1)), ("e", Some(2))] {
+            db.add_task(text).await.unwrap();
+            let mut task = db.get_tasks().await.unwrap().remove(0);
+            task.priority = priority;
+            db.update_task(&task).await.unwrap();
+        }
+        assert_eq!(open_texts(&db).await, ["e", "a", "b", "c", "d"]);
+
+        db.set_group_by_priority(true).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["a", "e", "c", "b", "d"]);
+
+        // Moves stay inside the bucket, whatever the index
+        let tasks = db.get_tasks().await.unwrap();
+        let id_of = |text: &str| tasks.iter().find(|t| t.text == text).unwrap().id;
+        db.move_task(id_of("c"), 0).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["a", "c", "e", "b", "d"]);
+        db.move_task(id_of("b"), 99).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["a", "c", "e", "d", "b"]);
+
+        // The manual order within each bucket is kept when grouping is off again
+        db.set_group_by_priority(false).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["a", "c", "e", "d", "b"]);
+    }
+
+    #[tokio::test]
+    async fn completes_reopens_and_archives_tasks() {
+        let db = memory_db().await;
+        for text in ["a", "b", "c"] {
+            db.add_task(text).await.unwrap();
+        }
+        let texts = |tasks: Vec<Task>| tasks.into_iter().map(|t| t.text).collect::<Vec<_>>();
+        let tasks = db.get_tasks().await.unwrap();
+        let id_of = |text: &str| tasks.iter().find(|t| t.text == text).unwrap().id;
+
+        db.complete_task(id_of("a")).await.unwrap();
+        db.complete_task(id_of("b")).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["c"]);
+        let completed = db.get_tasks_with(TaskFilter::Completed).await.unwrap();
+        assert_eq!(completed.len(), 2);
+        assert!(completed.iter().all(|t| t.completed_at.is_some()));
+
+        db.reopen_task(id_of("b")).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["c", "b"]);
+        assert!(db.get_task(id_of("b")).await.unwrap().unwrap().completed_at.is_none());
+        assert_eq!(texts(db.get_tasks_with(TaskFilter::Completed).await.unwrap()), ["a"]);
+
+        // Archived tasks leave the open and completed lists alike
+        db.archive_completed().await.unwrap();
+        assert_eq!(open_texts(&db).await, ["c", "b"]);
+        assert!(db.get_tasks_with(TaskFilter::Completed).await.unwrap().is_empty());
+        assert_eq!(texts(db.get_tasks_with(TaskFilter::Archived).await.unwrap()), ["a"]);
+
+        db.reopen_task(id_of("a")).await.unwrap();
+        assert_eq!(open_texts(&db).await, ["c", "b", "a"]);
+        assert!(db.get_tasks_with(TaskFilter::Archived).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn edits_task_details() {
+        let db = memory_db().await;
+        db.add_task("draft").await.unwrap();
+        let mut task = db.get_tasks().await.unwrap().remove(0);
+        assert_eq!((task.notes.as_deref(), task.due_date, task.priority), (None, None, None));
+
+        task.text = "Write report".to_string();
+        task.notes = Some("Numbers from Q3".to_string());
+        task.due_date = NaiveDate::from_ymd_opt(2024, 3, 5);
+        task.priority = Some(1);
+        task.estimate = Some(4);
+        db.update_task(&task).await.unwrap();
 
-        // Actually fetch_one returns Result<Row>. query_scalar returns Result<T>.
-        // If no rows match, SUM returns NULL, which maps to Option::None.
+        let saved = db.get_task(task.id).await.unwrap().unwrap();
+        assert_eq!(saved.text, "Write report");
+        assert_eq!(saved.notes.as_deref(), Some("Numbers from Q3"));
+        assert_eq!(saved.due_date, NaiveDate::from_ymd_opt(2024, 3, 5));
+        assert_eq!((saved.priority, saved.estimate), (Some(1), Some(4)));
 
-        Ok(result.unwrap_or(0))
+        task.id += 1;
+        assert!(matches!(db.update_task(&task).await, Err(sqlx::Error::RowNotFound)));
     }
 }
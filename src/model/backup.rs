@@ -0,0 +1,334 @@
+//! Backups are `VACUUM INTO` snapshots, which SQLite takes inside a read
+//! transaction, so they are consistent even while the app keeps writing.
+//! Restoring swaps the database file for an upgraded copy of the backup,
+//! once nothing else has the database open.
+
+use super::migrations::{self, LATEST_VERSION};
+use super::Database;
+use chrono::NaiveDate;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool};
+use sqlx::{ConnectOptions, Connection};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Automatic backups kept before the oldest ones are deleted.
+pub const AUTO_BACKUPS_KEPT: usize = 7;
+
+const AUTO_PREFIX: &str = "auto-";
+
+#[derive(Debug)]
+pub enum BackupError {
+    /// The file is not a pomimi database, or not an SQLite file at all.
+    NotADatabase(PathBuf),
+    /// The file was written by a newer pomimi with a schema this build does not know.
+    NewerSchema { path: PathBuf, version: i64 },
+    /// Another connection, most likely a running pomimi, has the database open.
+    InUse(PathBuf),
+    Io(PathBuf, io::Error),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::NotADatabase(path) => write!(f, "{} is not a pomimi database", path.display()),
+            BackupError::NewerSchema { path, version } => write!(
+                f,
+                "{} was written by a newer version of pomimi (schema version {}, this build supports up to {}); update pomimi to restore it",
+                path.display(),
+                version,
+                LATEST_VERSION
+            ),
+            BackupError::InUse(path) => write!(f, "{} is in use; quit pomimi and try again", path.display()),
+            BackupError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            BackupError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for BackupError {
+    fn from(e: sqlx::Error) -> Self {
+        BackupError::Database(e)
+    }
+}
+
+impl Database {
+    /// Writes a snapshot of the database to `path`, which must not exist yet.
+    pub async fn backup_to(&self, path: &Path) -> Result<(), BackupError> {
+        if path.exists() {
+            return Err(BackupError::Io(path.to_path_buf(), io::ErrorKind::AlreadyExists.into()));
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| BackupError::Io(dir.to_path_buf(), e))?;
+        }
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Takes the automatic backup for `today` in `dir` unless it already
+    /// exists, then deletes all but the newest [`AUTO_BACKUPS_KEPT`].
+    /// Returns the new backup, if one was taken.
+    pub async fn auto_backup(&self, dir: &Path, today: NaiveDate) -> Result<Option<PathBuf>, BackupError> {
+        let path = dir.join(format!("{}{}.db", AUTO_PREFIX, today.format("%Y-%m-%d")));
+        if path.exists() {
+            return Ok(None);
+        }
+        self.backup_to(&path).await?;
+        prune_auto_backups(dir, AUTO_BACKUPS_KEPT).map_err(|e| BackupError::Io(dir.to_path_buf(), e))?;
+        Ok(Some(path))
+    }
+}
+
+/// Automatic backups are named by date, so name order is age order.
+fn prune_auto_backups(dir: &Path, keep: usize) -> io::Result<()> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(AUTO_PREFIX) && name.ends_with(".db"))
+        })
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for old in &backups[..excess] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+async fn open_file(path: &Path, read_only: bool) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(read_only);
+    SqlitePool::connect_with(options).await
+}
+
+/// Checks that `path` holds a pomimi database this build can restore.
+pub async fn check_backup(path: &Path) -> Result<(), BackupError> {
+    fs::metadata(path).map_err(|e| BackupError::Io(path.to_path_buf(), e))?;
+    let version = backup_version(path).await?;
+    if version > LATEST_VERSION {
+        return Err(BackupError::NewerSchema { path: path.to_path_buf(), version });
+    }
+    Ok(())
+}
+
+/// Schema version of the pomimi database at `path`.
+async fn backup_version(path: &Path) -> Result<i64, BackupError> {
+    let not_a_database = |_| BackupError::NotADatabase(path.to_path_buf());
+    let pool = open_file(path, true).await.map_err(not_a_database)?;
+    let mut conn = pool.acquire().await.map_err(not_a_database)?;
+    let version = migrations::current_version(&mut conn).await.map_err(not_a_database)?;
+    let has_tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'tasks'")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(not_a_database)?;
+    drop(conn);
+    pool.close().await;
+    if has_tasks == 0 {
+        return Err(BackupError::NotADatabase(path.to_path_buf()));
+    }
+    Ok(version)
+}
+
+/// Folds the write-ahead log of the database at `path` back into it and
+/// removes the `-wal`, `-shm` and `-journal` files, so they cannot be
+/// replayed onto a file swapped in at the same path. SQLite only leaves WAL mode when no
+/// other connection has the database open, which is how a running pomimi
+/// is detected.
+async fn release(path: &Path) -> Result<(), BackupError> {
+    if path.exists() {
+        let in_use = |_| BackupError::InUse(path.to_path_buf());
+        let mut conn: SqliteConnection = SqliteConnectOptions::new()
+            .filename(path)
+            .busy_timeout(Duration::from_millis(500))
+            .connect()
+            .await?;
+        let mode: Result<String, _> = sqlx::query_scalar("PRAGMA journal_mode = DELETE").fetch_one(&mut conn).await;
+        conn.close().await?;
+        if !mode.map_err(in_use)?.eq_ignore_ascii_case("delete") {
+            return Err(BackupError::InUse(path.to_path_buf()));
+        }
+    }
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        match fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(BackupError::Io(sidecar.into(), e)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the database at `to` with the backup at `from`, upgraded to the
+/// current schema. Backups from a newer pomimi are refused, and so is a
+/// database that is still open elsewhere. Nothing at `to` changes unless the
+/// whole restore succeeds.
+pub async fn restore(from: &Path, to: &Path) -> Result<(), BackupError> {
+    check_backup(from).await?;
+
+    // Copy through SQLite rather than the file system, so a backup that is
+    // itself in use still comes across whole
+    let staging = to.with_extension("db.restore");
+    if staging.exists() {
+        fs::remove_file(&staging).map_err(|e| BackupError::Io(staging.clone(), e))?;
+    }
+    let source = Database { pool: open_file(from, true).await? };
+    source.backup_to(&staging).await?;
+    source.pool.close().await;
+
+    let upgraded = open_file(&staging, false).await?;
+    let migrated = migrations::run(&upgraded).await;
+    upgraded.close().await;
+    if let Err(e) = migrated {
+        let _ = fs::remove_file(&staging);
+        return Err(e.into());
+    }
+
+    if let Err(e) = release(to).await {
+        let _ = fs::remove_file(&staging);
+        return Err(e);
+    }
+    fs::rename(&staging, to).map_err(|e| BackupError::Io(to.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("pomimi-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn file_db(path: &Path) -> Database {
+        Database::open(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap()
+    }
+
+    async fn task_texts(db: &Database) -> Vec<String> {
+        db.get_tasks().await.unwrap().into_iter().map(|t| t.text).collect()
+    }
+
+    #[tokio::test]
+    async fn restores_a_snapshot_taken_while_open() {
+        let dir = TempDir::new("backup-round-trip");
+        let live = dir.0.join("pomimi.db");
+        let db = file_db(&live).await;
+        db.add_task("write report").await.unwrap();
+        db.backup_to(&dir.0.join("backup.db")).await.unwrap();
+        db.add_task("after the backup").await.unwrap();
+        assert!(db.backup_to(&dir.0.join("backup.db")).await.is_err());
+        db.close().await;
+
+        restore(&dir.0.join("backup.db"), &live).await.unwrap();
+        assert_eq!(task_texts(&file_db(&live).await).await, vec!["write report"]);
+        assert!(!dir.0.join("pomimi.db.restore").exists());
+    }
+
+    #[tokio::test]
+    async fn restores_over_a_database_with_a_pending_wal() {
+        let dir = TempDir::new("backup-wal");
+        let db = file_db(&dir.0.join("source.db")).await;
+        db.add_task("write report").await.unwrap();
+        db.backup_to(&dir.0.join("backup.db")).await.unwrap();
+        db.add_task("only in the log").await.unwrap();
+
+        // Copied while still open, the log holds writes the main file lacks,
+        // like a database left behind by a crash
+        let live = dir.0.join("pomimi.db");
+        for suffix in ["", "-wal", "-shm"] {
+            fs::copy(dir.0.join(format!("source.db{}", suffix)), dir.0.join(format!("pomimi.db{}", suffix))).unwrap();
+        }
+        db.close().await;
+        assert!(fs::metadata(dir.0.join("pomimi.db-wal")).unwrap().len() > 0);
+
+        restore(&dir.0.join("backup.db"), &live).await.unwrap();
+        assert!(!dir.0.join("pomimi.db-wal").exists() && !dir.0.join("pomimi.db-shm").exists());
+        let restored = file_db(&live).await;
+        assert_eq!(task_texts(&restored).await, vec!["write report"]);
+        let check: String = sqlx::query_scalar("PRAGMA integrity_check").fetch_one(&restored.pool).await.unwrap();
+        assert_eq!(check, "ok");
+    }
+
+    #[tokio::test]
+    async fn refuses_to_restore_over_an_open_database() {
+        let dir = TempDir::new("backup-in-use");
+        let live = dir.0.join("pomimi.db");
+        let db = file_db(&live).await;
+        db.add_task("keep me").await.unwrap();
+        db.backup_to(&dir.0.join("backup.db")).await.unwrap();
+        db.add_task("still here").await.unwrap();
+
+        let error = restore(&dir.0.join("backup.db"), &live).await.unwrap_err();
+        assert!(matches!(error, BackupError::InUse(_)), "{}", error);
+        assert!(!dir.0.join("pomimi.db.restore").exists());
+        assert_eq!(task_texts(&db).await, vec!["still here", "keep me"]);
+    }
+
+    #[tokio::test]
+    async fn refuses_backups_from_a_newer_schema() {
+        let dir = TempDir::new("backup-newer");
+        let live = dir.0.join("pomimi.db");
+        let db = file_db(&live).await;
+        db.add_task("keep me").await.unwrap();
+
+        let newer = dir.0.join("newer.db");
+        db.backup_to(&newer).await.unwrap();
+        let pool = open_file(&newer, false).await.unwrap();
+        sqlx::query("UPDATE schema_version SET version = ?").bind(LATEST_VERSION + 1).execute(&pool).await.unwrap();
+        pool.close().await;
+
+        let error = restore(&newer, &live).await.unwrap_err();
+        assert!(matches!(error, BackupError::NewerSchema { version, .. } if version == LATEST_VERSION + 1));
+        assert!(error.to_string().contains("newer version of pomimi"));
+        assert_eq!(task_texts(&db).await, vec!["keep me"]);
+
+        fs::write(dir.0.join("notes.txt"), "not a database").unwrap();
+        assert!(matches!(restore(&dir.0.join("notes.txt"), &live).await, Err(BackupError::NotADatabase(_))));
+    }
+
+    #[tokio::test]
+    async fn keeps_one_automatic_backup_per_day() {
+        let dir = TempDir::new("backup-rotation");
+        let db = file_db(&dir.0.join("pomimi.db")).await;
+        let backups = dir.0.join("backups");
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+
+        for d in 1..=9 {
+            assert!(db.auto_backup(&backups, day(d)).await.unwrap().is_some());
+        }
+        assert_eq!(db.auto_backup(&backups, day(9)).await.unwrap(), None);
+        // Manual backups in the same folder are left alone
+        db.backup_to(&backups.join("manual.db")).await.unwrap();
+        db.auto_backup(&backups, day(10)).await.unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(&backups).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), AUTO_BACKUPS_KEPT + 1);
+        assert_eq!((names[0].as_str(), names[AUTO_BACKUPS_KEPT - 1].as_str()), ("auto-2024-03-04.db", "auto-2024-03-10.db"));
+        assert_eq!(names[AUTO_BACKUPS_KEPT], "manual.db");
+    }
+}
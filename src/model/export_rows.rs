@@ -0,0 +1,142 @@
+//! Rows for exporting sessions and tasks, in a stable order so repeated
+//! exports of the same data are identical.
+
+use super::stats::start_of_day;
+use super::Database;
+use chrono::{Local, NaiveDate, TimeZone};
+
+/// Days to export, both ends included; an open end is unbounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    /// `[from, until)` timestamps, with days starting at `day_start_hour`
+    /// like every other focus total.
+    fn bounds<Tz: TimeZone>(&self, day_start_hour: u8, tz: &Tz) -> (i64, i64) {
+        let from = self.from.map_or(i64::MIN, |day| start_of_day(day, day_start_hour, tz));
+        let until = self.to
+            .and_then(|day| day.succ_opt())
+            .map_or(i64::MAX, |day| start_of_day(day, day_start_hour, tz));
+        (from, until)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct SessionRecord {
+    pub id: i64,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub duration_seconds: i64,
+    pub planned_seconds: Option<i64>,
+    pub task_id: Option<i64>,
+    /// `None` for sessions without a task, or whose task was deleted.
+    pub task: Option<String>,
+    pub completed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct TaskRecord {
+    pub id: i64,
+    pub text: String,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub archived_at: Option<i64>,
+    /// Focus time spent on the task within the range.
+    pub focus_seconds: i64,
+}
+
+impl Database {
+    /// Focus sessions that started within `range`, oldest first.
+    pub async fn export_sessions(&self, range: DateRange) -> Result<Vec<SessionRecord>, sqlx::Error> {
+        let day_start_hour = self.day_start_hour().await?;
+        self.export_sessions_in(range, day_start_hour, &Local).await
+    }
+
+    async fn export_sessions_in<Tz: TimeZone>(&self, range: DateRange, day_start_hour: u8, tz: &Tz) -> Result<Vec<SessionRecord>, sqlx::Error> {
+        let (from, until) = range.bounds(day_start_hour, tz);
+        // Sessions from before end times were stored are assumed to have run uninterrupted
+        sqlx::query_as::<_, SessionRecord>(
+            "SELECT s.id, s.start_time AS started_at,
+                    COALESCE(s.end_time, s.start_time + s.duration_seconds) AS ended_at,
+                    s.duration_seconds, s.planned_seconds, s.task_id, t.text AS task, s.completed
+             FROM sessions s LEFT JOIN tasks t ON t.id = s.task_id
+             WHERE s.start_time >= ? AND s.start_time < ?
+             ORDER BY s.start_time, s.id"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Tasks created, completed or worked on within `range`, by id.
+    pub async fn export_tasks(&self, range: DateRange) -> Result<Vec<TaskRecord>, sqlx::Error> {
+        let day_start_hour = self.day_start_hour().await?;
+        self.export_tasks_in(range, day_start_hour, &Local).await
+    }
+
+    async fn export_tasks_in<Tz: TimeZone>(&self, range: DateRange, day_start_hour: u8, tz: &Tz) -> Result<Vec<TaskRecord>, sqlx::Error> {
+        let (from, until) = range.bounds(day_start_hour, tz);
+        sqlx::query_as::<_, TaskRecord>(
+            "SELECT t.id, t.text, t.created_at, t.completed_at, t.archived_at,
+                    COALESCE(SUM(s.duration_seconds), 0) AS focus_seconds
+             FROM tasks t
+             LEFT JOIN sessions s ON s.task_id = t.id AND s.start_time >= ?1 AND s.start_time < ?2
+             GROUP BY t.id
+             HAVING (t.created_at >= ?1 AND t.created_at < ?2)
+                 OR (t.completed_at >= ?1 AND t.completed_at < ?2)
+                 OR COUNT(s.id) > 0
+             ORDER BY t.id"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::FocusSession;
+    use chrono::FixedOffset;
+
+    #[tokio::test]
+    async fn filters_by_local_day() {
+        let db = crate::model::tests::memory_db().await;
+        let tz = FixedOffset::west_opt(3 * 3600).unwrap();
+        let at = |d: u32, h: u32| tz.with_ymd_and_hms(2024, 3, d, h, 0, 0).unwrap().timestamp();
+
+        db.add_task("old").await.unwrap();
+        db.add_task("report").await.unwrap();
+        sqlx::query("UPDATE tasks SET created_at = ?").bind(at(1, 9)).execute(&db.pool).await.unwrap();
+        let report = db.get_tasks().await.unwrap()[0].id;
+        for (start, task_id) in [(at(4, 23), Some(report)), (at(5, 22), Some(report)), (at(6, 9), None)] {
+            let session = FocusSession {
+                task_id,
+                planned_seconds: 1500,
+                actual_seconds: 1500,
+                started_at: start,
+                ended_at: start + 1500,
+                completed: true,
+            };
+            db.add_session(&session).await.unwrap();
+        }
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5);
+        let range = DateRange { from: day, to: day };
+        let sessions = db.export_sessions_in(range, 0, &tz).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!((sessions[0].started_at, sessions[0].task.as_deref()), (at(5, 22), Some("report")));
+
+        let tasks = db.export_tasks_in(range, 0, &tz).await.unwrap();
+        assert_eq!(tasks.iter().map(|t| (t.text.as_str(), t.focus_seconds)).collect::<Vec<_>>(), vec![("report", 1500)]);
+
+        // Open ends take everything
+        assert_eq!(db.export_sessions_in(DateRange::default(), 0, &tz).await.unwrap().len(), 3);
+        assert_eq!(db.export_tasks_in(DateRange::default(), 0, &tz).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! Task lists from plain text (one task per line), Markdown checklists and
+//! todo.txt, for `pomimi task import` and multi-line pastes in the GUI.
+
+use super::{projects, Database, LOWEST_PRIORITY};
+use chrono::NaiveDate;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// One task per non-empty line.
+    Plain,
+    /// `- [ ]` and `- [x]` items; plain bullets count as open tasks.
+    Markdown,
+    /// <https://github.com/todotxt/todo.txt> lines.
+    TodoTxt,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the lines themselves: list markers mean
+    /// Markdown, todo.txt markers mean todo.txt, anything else is plain.
+    pub fn detect(input: &str) -> ImportFormat {
+        let lines = || input.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines().any(|line| list_item(line).is_some()) {
+            ImportFormat::Markdown
+        } else if lines().any(|line| {
+            let todo = parse_todo_txt(line);
+            todo.done || todo.priority.is_some() || !todo.projects.is_empty() || !todo.contexts.is_empty()
+        }) {
+            ImportFormat::TodoTxt
+        } else {
+            ImportFormat::Plain
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportedTask {
+    pub text: String,
+    pub done: bool,
+    /// todo.txt priority, `A` being the highest.
+    pub priority: Option<char>,
+    /// todo.txt `due:` tag.
+    pub due_date: Option<NaiveDate>,
+    /// todo.txt `+project` tags, without the `+`.
+    pub projects: Vec<String>,
+    /// todo.txt `@context` tags, without the `@`.
+    pub contexts: Vec<String>,
+}
+
+/// Parses `input` line by line, in order, skipping lines that hold no task.
+pub fn parse_tasks(input: &str, format: ImportFormat) -> Vec<ImportedTask> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match format {
+            ImportFormat::Plain => Some(ImportedTask { text: line.to_string(), ..ImportedTask::default() }),
+            ImportFormat::Markdown => parse_markdown(line),
+            ImportFormat::TodoTxt => Some(parse_todo_txt(line)),
+        })
+        .filter(|task| !task.text.is_empty())
+        .collect()
+}
+
+/// The text after a `-`, `*`, `+` or `1.` list marker.
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(rest) = line.strip_prefix(['-', '*', '+']) {
+        return rest.strip_prefix(' ');
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..].strip_prefix(['.', ')'])?.strip_prefix(' ')
+}
+
+/// List items become tasks; headings and prose in between are skipped.
+fn parse_markdown(line: &str) -> Option<ImportedTask> {
+    let item = list_item(line)?.trim_start();
+    let (done, text) = if let Some(text) = item.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = item.strip_prefix("[x]").or_else(|| item.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        (false, item)
+    };
+    Some(ImportedTask { text: text.trim().to_string(), done, ..ImportedTask::default() })
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+/// Strips the completion mark, priority, dates and a `due:` tag; `+project`
+/// and `@context` tags are collected but stay in the text, as todo.txt shows
+/// them.
+fn parse_todo_txt(line: &str) -> ImportedTask {
+    let mut rest = line;
+    let done = match rest.strip_prefix("x ") {
+        Some(after) => {
+            rest = after.trim_start();
+            true
+        }
+        None => false,
+    };
+
+    let mut priority = None;
+    let bytes = rest.as_bytes();
+    if bytes.len() >= 4 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')' && bytes[3] == b' ' {
+        priority = Some(bytes[1] as char);
+        rest = rest[4..].trim_start();
+    }
+
+    // Completion date (done tasks only), then creation date
+    for _ in 0..if done { 2 } else { 1 } {
+        match rest.split_once(' ') {
+            Some((word, after)) if parse_date(word).is_some() => rest = after.trim_start(),
+            _ => break,
+        }
+    }
+
+    let tags = |sigil: char| {
+        rest.split_whitespace()
+            .filter_map(|word| word.strip_prefix(sigil))
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let due_date = rest.split_whitespace().find_map(|word| parse_date(word.strip_prefix("due:")?));
+    let text: Vec<&str> = rest
+        .split_whitespace()
+        .filter(|word| word.strip_prefix("due:").is_none_or(|date| parse_date(date).is_none()))
+        .collect();
+    ImportedTask {
+        text: text.join(" "),
+        done,
+        priority,
+        due_date,
+        projects: tags('+'),
+        contexts: tags('@'),
+    }
+}
+
+impl Database {
+    /// Inserts `tasks` in one transaction, above the existing open tasks and
+    /// in the order given. The first todo.txt `+project` becomes the task's
+    /// project and `@contexts` become tags. Returns how many were added.
+    pub async fn import_tasks(&self, tasks: &[ImportedTask]) -> Result<usize, sqlx::Error> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        let top: i64 = sqlx::query_scalar("SELECT COALESCE(MIN(position), 0) FROM tasks")
+            .fetch_one(&mut *tx)
+            .await?;
+        let first = top - tasks.len() as i64;
+        for (i, task) in tasks.iter().enumerate() {
+            let project_id = match task.projects.first() {
+                Some(name) => Some(projects::project_id(&mut tx, name).await?),
+                None => None,
+            };
+            let inserted = sqlx::query(
+                "INSERT INTO tasks (text, completed, created_at, completed_at, position, priority, due_date, project_id)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&task.text)
+            .bind(task.done)
+            .bind(now)
+            .bind(task.done.then_some(now))
+            .bind(first + i as i64)
+            // (A) is P1 and (B) is P2; everything from (C) down is P3
+            .bind(task.priority.map(|p| (p as u8 - b'A' + 1).min(LOWEST_PRIORITY)))
+            .bind(task.due_date)
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await?;
+            projects::replace_tags(&mut tx, inserted.last_insert_rowid(), &task.contexts).await?;
+        }
+        tx.commit().await?;
+        Ok(tasks.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TaskFilter;
+
+    fn texts(tasks: &[ImportedTask]) -> Vec<&str> {
+        tasks.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_markdown_checklists() {
+        let input = "# Sprint\n\n- [ ] Write report\n  - [x] Collect numbers\n* Call Ana\n1. [ ]  Book room\nSome notes\n- [ ]\n";
+        let tasks = parse_tasks(input, ImportFormat::Markdown);
+        assert_eq!(texts(&tasks), vec!["Write report", "Collect numbers", "Call Ana", "Book room"]);
+        assert_eq!(tasks.iter().map(|t| t.done).collect::<Vec<_>>(), vec![false, true, false, false]);
+    }
+
+    #[test]
+    fn parses_todo_txt() {
+        let tasks = parse_tasks(
+            "(A) 2024-03-01 Call Mom +Family @phone\nx 2024-03-05 2024-03-01 Pay rent +Home due:2024-03-05\n(b) lowercase is not a priority\n",
+            ImportFormat::TodoTxt,
+        );
+        assert_eq!(tasks[0], ImportedTask {
+            text: "Call Mom +Family @phone".to_string(),
+            done: false,
+            priority: Some('A'),
+            due_date: None,
+            projects: vec!["Family".to_string()],
+            contexts: vec!["phone".to_string()],
+        });
+        assert_eq!((tasks[1].text.as_str(), tasks[1].done), ("Pay rent +Home", true));
+        assert_eq!(tasks[1].due_date, NaiveDate::from_ymd_opt(2024, 3, 5));
+        assert_eq!((tasks[2].text.as_str(), tasks[2].priority), ("(b) lowercase is not a priority", None));
+    }
+
+    #[test]
+    fn detects_the_format() {
+        assert_eq!(ImportFormat::detect("- [ ] one\n- [ ] two"), ImportFormat::Markdown);
+        assert_eq!(ImportFormat::detect("(A) one\ntwo @home"), ImportFormat::TodoTxt);
+        assert_eq!(ImportFormat::detect("one\ntwo"), ImportFormat::Plain);
+        assert_eq!(texts(&parse_tasks("  one \n\n two", ImportFormat::Plain)), vec!["one", "two"]);
+    }
+
+    #[tokio::test]
+    async fn imports_above_existing_tasks_in_order() {
+        let db = crate::model::tests::memory_db().await;
+        db.add_task("existing").await.unwrap();
+        let tasks = parse_tasks("- [ ] first\n- [x] done\n- [ ] second", ImportFormat::Markdown);
+        assert_eq!(db.import_tasks(&tasks).await.unwrap(), 3);
+
+        let open: Vec<String> = db.get_tasks().await.unwrap().into_iter().map(|t| t.text).collect();
+        assert_eq!(open, vec!["first", "second", "existing"]);
+        let done = db.get_tasks_with(TaskFilter::Completed).await.unwrap();
+        assert_eq!(done.iter().map(|t| t.text.as_str()).collect::<Vec<_>>(), vec!["done"]);
+    }
+
+    #[tokio::test]
+    async fn stores_todo_txt_priorities_and_due_dates() {
+        let db = crate::model::tests::memory_db().await;
+        let tasks = parse_tasks("(A) now +Work @phone @quick
+(B) soon due:2024-03-05 +work
+(E) someday
+whenever", ImportFormat::TodoTxt);
+        db.import_tasks(&tasks).await.unwrap();
+
+        let saved = db.get_tasks().await.unwrap();
+        assert_eq!(
+            saved.iter().map(|t| (t.text.as_str(), t.priority)).collect::<Vec<_>>(),
+            vec![("now +Work @phone @quick", Some(1)), ("soon +work", Some(2)), ("someday", Some(3)), ("whenever", None)]
+        );
+        assert_eq!(saved[1].due_date, NaiveDate::from_ymd_opt(2024, 3, 5));
+        // Projects are matched without case
+        assert_eq!((saved[0].project.as_deref(), saved[1].project_id), (Some("Work"), saved[0].project_id));
+        assert_eq!(saved[0].tags, ["phone", "quick"]);
+    }
+}
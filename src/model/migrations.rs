@@ -0,0 +1,356 @@
+//! Numbered schema migrations.
+//!
+//! The current version lives in the single-row `schema_version` table. A
+//! database without that table is version 0: either brand new or created by
+//! a build that predates migrations (the original `CREATE TABLE IF NOT EXISTS`
+//! schema). Pending migrations run in order inside one transaction, so a
+//! failed upgrade leaves the file untouched.
+
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+
+enum Step {
+    Sql(&'static str),
+    /// `ALTER TABLE .. ADD COLUMN`, skipped when the column is already there
+    /// (databases touched by pre-migration builds may have some of them).
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+struct Migration {
+    version: i64,
+    steps: &'static [Step],
+}
+
+const MIGRATIONS: &[Migration] = &[
+    // 1: the original schema, so fresh databases and version 0 ones converge
+    Migration {
+        version: 1,
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS tasks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    text TEXT NOT NULL,
+                    completed BOOLEAN NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL
+                )",
+            ),
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS preferences (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                )",
+            ),
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS sessions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    start_time INTEGER NOT NULL,
+                    duration_seconds INTEGER NOT NULL
+                )",
+            ),
+        ],
+    },
+    // 2: task completion/archive and session attribution
+    Migration {
+        version: 2,
+        steps: &[
+            Step::AddColumn { table: "tasks", column: "completed_at", definition: "INTEGER" },
+            Step::AddColumn { table: "tasks", column: "archived_at", definition: "INTEGER" },
+            Step::AddColumn { table: "sessions", column: "task_id", definition: "INTEGER" },
+            Step::AddColumn { table: "sessions", column: "planned_seconds", definition: "INTEGER" },
+            Step::AddColumn { table: "sessions", column: "end_time", definition: "INTEGER" },
+            Step::AddColumn { table: "sessions", column: "completed", definition: "BOOLEAN NOT NULL DEFAULT 1" },
+        ],
+    },
+    // 3: manual task order, seeded with the old newest-first order
+    Migration {
+        version: 3,
+        steps: &[
+            Step::AddColumn { table: "tasks", column: "position", definition: "INTEGER NOT NULL DEFAULT 0" },
+            Step::Sql(
+                "UPDATE tasks SET position = (
+                    SELECT COUNT(*) FROM tasks AS newer
+                    WHERE newer.created_at > tasks.created_at
+                       OR (newer.created_at = tasks.created_at AND newer.id > tasks.id)
+                )",
+            ),
+        ],
+    },
+    // 4: task notes, due dates and priorities
+    Migration {
+        version: 4,
+        steps: &[
+            Step::AddColumn { table: "tasks", column: "notes", definition: "TEXT" },
+            Step::AddColumn { table: "tasks", column: "due_date", definition: "TEXT" },
+            Step::AddColumn { table: "tasks", column: "priority", definition: "INTEGER" },
+        ],
+    },
+    // 5: pomodoro estimates
+    Migration {
+        version: 5,
+        steps: &[
+            Step::AddColumn { table: "tasks", column: "estimate", definition: "INTEGER" },
+        ],
+    },
+    // 6: checklist steps under tasks
+    Migration {
+        version: 6,
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS subtasks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                    text TEXT NOT NULL,
+                    done BOOLEAN NOT NULL DEFAULT 0,
+                    position INTEGER NOT NULL DEFAULT 0
+                )",
+            ),
+            Step::Sql("CREATE INDEX IF NOT EXISTS subtasks_task_id ON subtasks (task_id)"),
+        ],
+    },
+    // 7: projects with colors, and free-form tags
+    Migration {
+        version: 7,
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS projects (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                    color TEXT NOT NULL
+                )",
+            ),
+            Step::AddColumn {
+                table: "tasks",
+                column: "project_id",
+                definition: "INTEGER REFERENCES projects(id) ON DELETE SET NULL",
+            },
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS task_tags (
+                    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                    tag TEXT NOT NULL COLLATE NOCASE,
+                    PRIMARY KEY (task_id, tag)
+                )",
+            ),
+        ],
+    },
+];
+
+/// Schema version written by this build.
+pub const LATEST_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Reads the schema version, treating a missing `schema_version` table as 0.
+pub async fn current_version(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    let has_table: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'"
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if has_table == 0 {
+        return Ok(0);
+    }
+
+    let version: Option<i64> = sqlx::query_scalar("SELECT version FROM schema_version WHERE id = 1")
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Brings the database up to [`LATEST_VERSION`]. Refuses files written by a
+/// newer build rather than guessing at their schema.
+pub async fn run(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let current = current_version(&mut tx).await?;
+    if current > LATEST_VERSION {
+        return Err(sqlx::Error::Protocol(format!(
+            "database schema version {} is newer than this build supports ({})",
+            current, LATEST_VERSION
+        )));
+    }
+    if current == LATEST_VERSION {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            version INTEGER NOT NULL
+        )"
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        for step in migration.steps {
+            match step {
+                Step::Sql(sql) => {
+                    sqlx::query(sql).execute(&mut *tx).await?;
+                }
+                Step::AddColumn { table, column, definition } => {
+                    add_column_if_missing(&mut tx, table, column, definition).await?;
+                }
+            }
+        }
+    }
+
+    sqlx::query(
+        "INSERT INTO schema_version (id, version) VALUES (1, ?)
+         ON CONFLICT(id) DO UPDATE SET version = excluded.version"
+    )
+    .bind(LATEST_VERSION)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+async fn add_column_if_missing(conn: &mut SqliteConnection, table: &str, column: &str, definition: &str) -> Result<(), sqlx::Error> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?"
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if exists == 0 {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory_pool() -> SqlitePool {
+        // One connection, otherwise every connection gets its own in-memory database
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    /// The schema as created by `Database::new` before migrations existed.
+    async fn create_version_zero(pool: &SqlitePool) {
+        for sql in [
+            "CREATE TABLE tasks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                text TEXT NOT NULL,
+                completed BOOLEAN NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL
+            )",
+            "CREATE TABLE preferences (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            "CREATE TABLE sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                start_time INTEGER NOT NULL,
+                duration_seconds INTEGER NOT NULL
+            )",
+            "INSERT INTO tasks (text, completed, created_at) VALUES ('write report', 0, 100), ('call bob', 0, 200)",
+            "INSERT INTO preferences (key, value) VALUES ('theme_mode', 'light')",
+            "INSERT INTO sessions (start_time, duration_seconds) VALUES (1000, 1500)",
+        ] {
+            sqlx::query(sql).execute(pool).await.unwrap();
+        }
+    }
+
+    async fn version(pool: &SqlitePool) -> i64 {
+        let mut conn = pool.acquire().await.unwrap();
+        current_version(&mut conn).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn upgrades_version_zero_database_without_losing_data() {
+        let pool = memory_pool().await;
+        create_version_zero(&pool).await;
+        assert_eq!(version(&pool).await, 0);
+
+        run(&pool).await.unwrap();
+        assert_eq!(version(&pool).await, LATEST_VERSION);
+
+        let tasks: Vec<(i64, String, Option<i64>)> = sqlx::query_as("SELECT id, text, completed_at FROM tasks ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tasks, vec![(1, "write report".to_string(), None), (2, "call bob".to_string(), None)]);
+
+        let theme: String = sqlx::query_scalar("SELECT value FROM preferences WHERE key = 'theme_mode'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(theme, "light");
+
+        let session: (i64, i64, Option<i64>, bool) = sqlx::query_as("SELECT start_time, duration_seconds, task_id, completed FROM sessions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(session, (1000, 1500, None, true));
+    }
+
+    #[tokio::test]
+    async fn seeds_task_positions_newest_first() {
+        let pool = memory_pool().await;
+        create_version_zero(&pool).await;
+        run(&pool).await.unwrap();
+
+        let order: Vec<String> = sqlx::query_scalar("SELECT text FROM tasks ORDER BY position")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(order, vec!["call bob".to_string(), "write report".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn creates_latest_schema_on_empty_database() {
+        let pool = memory_pool().await;
+        run(&pool).await.unwrap();
+        assert_eq!(version(&pool).await, LATEST_VERSION);
+
+        sqlx::query("INSERT INTO sessions (start_time, end_time, duration_seconds, planned_seconds, task_id, completed) VALUES (1, 2, 3, 4, 5, 0)")
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn running_twice_is_a_no_op() {
+        let pool = memory_pool().await;
+        create_version_zero(&pool).await;
+        run(&pool).await.unwrap();
+        run(&pool).await.unwrap();
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(version(&pool).await, LATEST_VERSION);
+    }
+
+    #[tokio::test]
+    async fn tolerates_columns_added_before_migrations() {
+        let pool = memory_pool().await;
+        create_version_zero(&pool).await;
+        sqlx::query("ALTER TABLE tasks ADD COLUMN completed_at INTEGER").execute(&pool).await.unwrap();
+
+        run(&pool).await.unwrap();
+        assert_eq!(version(&pool).await, LATEST_VERSION);
+    }
+
+    #[tokio::test]
+    async fn refuses_newer_schema() {
+        let pool = memory_pool().await;
+        run(&pool).await.unwrap();
+        sqlx::query("UPDATE schema_version SET version = ?")
+            .bind(LATEST_VERSION + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = run(&pool).await.unwrap_err();
+        assert!(err.to_string().contains("newer than this build supports"));
+    }
+}
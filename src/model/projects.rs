@@ -0,0 +1,233 @@
+//! Projects, each with its own color, and free-form tags. A task belongs to
+//! at most one project and carries any number of tags; both come back with
+//! every [`Task`] the task queries load.
+
+use super::{format_hex_color, parse_hex_color, Database, Task};
+use sqlx::sqlite::SqliteConnection;
+
+/// Colors handed out to new projects in turn.
+pub const PROJECT_COLORS: [[u8; 3]; 6] = [
+    [0x4C, 0x9A, 0xFF],
+    [0x2E, 0xCC, 0x71],
+    [0xF3, 0x9C, 0x12],
+    [0x9B, 0x59, 0xB6],
+    [0x1A, 0xBC, 0x9C],
+    [0xE9, 0x1E, 0x63],
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    /// 8-bit RGB, like the accent color.
+    pub color: [u8; 3],
+}
+
+/// Narrows a task list to one project and/or one tag, for the filter bar
+/// and `pomimi task list --project`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabelFilter {
+    pub project_id: Option<i64>,
+    pub tag: Option<String>,
+}
+
+impl LabelFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        self.project_id.is_none_or(|id| task.project_id == Some(id))
+            && self.tag.as_ref().is_none_or(|tag| task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+}
+
+impl Task {
+    pub fn project_rgb(&self) -> Option<[u8; 3]> {
+        parse_hex_color(self.project_color.as_deref()?)
+    }
+}
+
+/// Splits `input` on commas and spaces into tags, dropping a leading `#`
+/// and repeats (ignoring case).
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split([',', ' ']).map(|tag| tag.trim().trim_start_matches('#')) {
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Id of the project called `name`, created with the next palette color
+/// when there is none yet.
+pub(super) async fn project_id(conn: &mut SqliteConnection, name: &str) -> Result<i64, sqlx::Error> {
+    let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM projects WHERE name = ?")
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects").fetch_one(&mut *conn).await?;
+    let color = PROJECT_COLORS[count as usize % PROJECT_COLORS.len()];
+    let result = sqlx::query("INSERT INTO projects (name, color) VALUES (?, ?)")
+        .bind(name)
+        .bind(format_hex_color(color))
+        .execute(&mut *conn)
+        .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Replaces the tags of `task_id`.
+pub(super) async fn replace_tags(conn: &mut SqliteConnection, task_id: i64, tags: &[String]) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM task_tags WHERE task_id = ?")
+        .bind(task_id)
+        .execute(&mut *conn)
+        .await?;
+    for tag in tags {
+        sqlx::query("INSERT OR IGNORE INTO task_tags (task_id, tag) VALUES (?, ?)")
+            .bind(task_id)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Every project, by name.
+    pub async fn get_projects(&self) -> Result<Vec<Project>, sqlx::Error> {
+        let rows: Vec<(i64, String, String)> = sqlx::query_as("SELECT id, name, color FROM projects ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, color)| Project { id, name, color: parse_hex_color(&color).unwrap_or(PROJECT_COLORS[0]) })
+            .collect())
+    }
+
+    /// The project called `name`, ignoring case.
+    pub async fn get_project(&self, name: &str) -> Result<Option<Project>, sqlx::Error> {
+        Ok(self.get_projects().await?.into_iter().find(|p| p.name.eq_ignore_ascii_case(name)))
+    }
+
+    /// Finds or creates the project called `name`; `color`, when given,
+    /// replaces its color.
+    pub async fn save_project(&self, name: &str, color: Option<[u8; 3]>) -> Result<Project, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let id = project_id(&mut conn, name).await?;
+        if let Some(color) = color {
+            sqlx::query("UPDATE projects SET color = ? WHERE id = ?")
+                .bind(format_hex_color(color))
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        drop(conn);
+        self.get_projects().await?.into_iter().find(|p| p.id == id).ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Deletes a project; its tasks are kept, without a project.
+    pub async fn delete_project(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM projects WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_task_project(&self, task_id: i64, project_id: Option<i64>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE tasks SET project_id = ? WHERE id = ?")
+            .bind(project_id)
+            .bind(task_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_task_tags(&self, task_id: i64, tags: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        replace_tags(&mut tx, task_id, tags).await?;
+        tx.commit().await
+    }
+
+    /// Tags on tasks that are not archived, by name.
+    pub async fn get_tags(&self) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT DISTINCT tag FROM task_tags JOIN tasks ON tasks.id = task_tags.task_id
+             WHERE tasks.archived_at IS NULL
+             ORDER BY tag"
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Fills in the tags of each of `tasks`.
+    pub(super) async fn load_tags(&self, tasks: &mut [Task]) -> Result<(), sqlx::Error> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+        let ids = serde_json::to_string(&tasks.iter().map(|t| t.id).collect::<Vec<_>>())
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        let tags: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT task_id, tag FROM task_tags
+             WHERE task_id IN (SELECT value FROM json_each(?))
+             ORDER BY tag"
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for task in tasks.iter_mut() {
+            task.tags = tags.iter().filter(|(id, _)| *id == task.id).map(|(_, tag)| tag.clone()).collect();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tags() {
+        assert_eq!(parse_tags("#home, errands  Home,,"), vec!["home", "errands"]);
+        assert!(parse_tags(" , #").is_empty());
+    }
+
+    #[tokio::test]
+    async fn filters_by_project_and_tag() {
+        let db = crate::model::tests::memory_db().await;
+        for text in ["call Ana", "write report", "buy milk"] {
+            db.add_task(text).await.unwrap();
+        }
+        let id_of = |tasks: &[Task], text: &str| tasks.iter().find(|t| t.text == text).unwrap().id;
+        let tasks = db.get_tasks().await.unwrap();
+
+        let work = db.save_project("Work", None).await.unwrap();
+        assert_eq!(work.color, PROJECT_COLORS[0]);
+        db.set_task_project(id_of(&tasks, "write report"), Some(work.id)).await.unwrap();
+        db.set_task_project(id_of(&tasks, "call Ana"), Some(work.id)).await.unwrap();
+        db.set_task_tags(id_of(&tasks, "call Ana"), &parse_tags("phone, #quick")).await.unwrap();
+        db.set_task_tags(id_of(&tasks, "buy milk"), &parse_tags("errands quick")).await.unwrap();
+
+        let tasks = db.get_tasks().await.unwrap();
+        let report = tasks.iter().find(|t| t.text == "write report").unwrap();
+        assert_eq!((report.project.as_deref(), report.project_rgb()), (Some("Work"), Some(PROJECT_COLORS[0])));
+        let shown = |filter: LabelFilter| -> Vec<&str> {
+            tasks.iter().filter(|t| filter.matches(t)).map(|t| t.text.as_str()).collect()
+        };
+        assert_eq!(shown(LabelFilter { project_id: Some(work.id), tag: None }), ["write report", "call Ana"]);
+        assert_eq!(shown(LabelFilter { project_id: None, tag: Some("QUICK".to_string()) }), ["buy milk", "call Ana"]);
+        assert_eq!(shown(LabelFilter { project_id: Some(work.id), tag: Some("quick".to_string()) }), ["call Ana"]);
+        assert_eq!(db.get_tags().await.unwrap(), ["errands", "phone", "quick"]);
+
+        // Names are matched without case; recoloring keeps the project
+        assert_eq!(db.save_project("work", Some(PROJECT_COLORS[2])).await.unwrap().id, work.id);
+        assert_eq!(db.get_project("WORK").await.unwrap().unwrap().color, PROJECT_COLORS[2]);
+
+        // Deleting the project keeps its tasks
+        db.delete_project(work.id).await.unwrap();
+        let tasks = db.get_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert!(tasks.iter().all(|t| t.project_id.is_none() && t.project.is_none()));
+    }
+}
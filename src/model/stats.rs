@@ -0,0 +1,520 @@
+//! Focus statistics: today's total and, for the Stats modal, totals per day
+//! and per hour of the day, the current streak, totals per task and project,
+//! and how well pomodoro estimates matched the pomodoros tasks took.
+//!
+//! Sessions are stored with Unix timestamps, so days are worked out here in
+//! the caller's time zone rather than in SQL. A day runs from the configured
+//! day-start hour to the same hour the next day; every query goes through
+//! [`day_of`] and [`start_of_day`] so they agree on where that is.
+
+use super::{parse_hex_color, Database};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeDelta, TimeZone, Timelike};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FocusStats {
+    /// Focus seconds per day, oldest first, ending today.
+    pub days: Vec<(NaiveDate, i64)>,
+    /// Focus seconds by the hour sessions started in, over the same days.
+    pub hours: [i64; 24],
+    /// Consecutive days with focus time, ending today (or yesterday, while
+    /// today has nothing yet).
+    pub streak: u32,
+    /// Totals per task over the same days, largest first.
+    pub tasks: Vec<TaskTotal>,
+    /// Totals per project over the same days, largest first.
+    pub projects: Vec<ProjectTotal>,
+}
+
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct TaskTotal {
+    pub task_id: Option<i64>,
+    /// `None` for sessions without a task, or whose task was deleted.
+    pub text: Option<String>,
+    pub seconds: i64,
+    pub sessions: i64,
+}
+
+impl TaskTotal {
+    pub fn label(&self) -> &str {
+        match (&self.text, self.task_id) {
+            (Some(text), _) => text,
+            (None, Some(_)) => "Deleted task",
+            (None, None) => "No task",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct ProjectTotal {
+    /// `None` for sessions whose task has no project, or no task at all.
+    pub project: Option<String>,
+    /// The project's color as `#RRGGBB`.
+    pub color: Option<String>,
+    pub seconds: i64,
+    pub sessions: i64,
+}
+
+impl ProjectTotal {
+    pub fn label(&self) -> &str {
+        self.project.as_deref().unwrap_or("No project")
+    }
+
+    pub fn rgb(&self) -> Option<[u8; 3]> {
+        parse_hex_color(self.color.as_deref()?)
+    }
+}
+
+/// Estimated against actual pomodoros for the tasks finished in one week.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EstimateWeek {
+    /// The Monday the week starts on.
+    pub start: NaiveDate,
+    /// Finished tasks that had an estimate.
+    pub tasks: u32,
+    pub estimated: u32,
+    pub actual: u32,
+    /// Tasks that took exactly the pomodoros estimated.
+    pub on_target: u32,
+}
+
+impl EstimateWeek {
+    /// Actual pomodoros per estimated one; above 1 means tasks took longer
+    /// than planned.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.estimated > 0).then(|| self.actual as f64 / self.estimated as f64)
+    }
+}
+
+/// The day `timestamp` counts towards in `tz`: its local date, or the day
+/// before when it falls before `day_start_hour`.
+fn day_of<Tz: TimeZone>(timestamp: i64, day_start_hour: u8, tz: &Tz) -> NaiveDate {
+    tz.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| (t.naive_local() - TimeDelta::hours(day_start_hour.into())).date())
+        .unwrap_or_default()
+}
+
+/// First instant of `date` in `tz`, at `day_start_hour` local time. Where a
+/// DST change skips that hour the day starts at the next hour that exists;
+/// where it repeats it, at the first of the two.
+pub(super) fn start_of_day<Tz: TimeZone>(date: NaiveDate, day_start_hour: u8, tz: &Tz) -> i64 {
+    let start = date.and_time(NaiveTime::MIN) + TimeDelta::hours(day_start_hour.into());
+    (0..24)
+        .find_map(|hour| tz.from_local_datetime(&(start + TimeDelta::hours(hour))).earliest())
+        .map_or_else(|| start.and_utc().timestamp(), |t| t.timestamp())
+}
+
+/// `[from, until)` timestamps of `date`.
+fn day_range<Tz: TimeZone>(date: NaiveDate, day_start_hour: u8, tz: &Tz) -> (i64, i64) {
+    let next = date.succ_opt().unwrap_or(date);
+    (start_of_day(date, day_start_hour, tz), start_of_day(next, day_start_hour, tz))
+}
+
+/// Sums `(start, seconds)` sessions into one entry per day from `first` to
+/// `last`, inclusive.
+fn day_totals<Tz: TimeZone>(
+    sessions: &[(i64, i64)],
+    first: NaiveDate,
+    last: NaiveDate,
+    day_start_hour: u8,
+    tz: &Tz,
+) -> Vec<(NaiveDate, i64)> {
+    let mut days: Vec<(NaiveDate, i64)> = first.iter_days().take_while(|day| *day <= last).map(|day| (day, 0)).collect();
+    for &(start, seconds) in sessions {
+        let date = day_of(start, day_start_hour, tz);
+        if let Some(day) = days.iter_mut().find(|(day, _)| *day == date) {
+            day.1 += seconds;
+        }
+    }
+    days
+}
+
+/// Buckets by the local clock hour, regardless of the day-start hour.
+fn hour_totals<Tz: TimeZone>(sessions: &[(i64, i64)], tz: &Tz) -> [i64; 24] {
+    let mut hours = [0; 24];
+    for &(start, seconds) in sessions {
+        if let Some(time) = tz.timestamp_opt(start, 0).single() {
+            hours[time.hour() as usize] += seconds;
+        }
+    }
+    hours
+}
+
+/// The Monday of the week `date` falls in.
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday().into())
+}
+
+/// Sums `(completed_at, estimate, actual)` tasks into one entry per week,
+/// starting with the week of `first`.
+fn estimate_weeks<Tz: TimeZone>(
+    finished: &[(i64, u32, u32)],
+    first: NaiveDate,
+    weeks: u32,
+    day_start_hour: u8,
+    tz: &Tz,
+) -> Vec<EstimateWeek> {
+    let first = week_start(first);
+    let mut report: Vec<EstimateWeek> = (0..weeks.max(1))
+        .map(|week| EstimateWeek {
+            start: first + Days::new(7 * u64::from(week)),
+            tasks: 0,
+            estimated: 0,
+            actual: 0,
+            on_target: 0,
+        })
+        .collect();
+    for &(completed_at, estimate, actual) in finished {
+        let start = week_start(day_of(completed_at, day_start_hour, tz));
+        if let Some(week) = report.iter_mut().find(|week| week.start == start) {
+            week.tasks += 1;
+            week.estimated += estimate;
+            week.actual += actual;
+            week.on_target += u32::from(estimate == actual);
+        }
+    }
+    report
+}
+
+/// Counts back from `today` over `active` days, newest first.
+fn streak(active: &[NaiveDate], today: NaiveDate) -> u32 {
+    let mut expected = if active.first() == Some(&today) { today } else { today.pred_opt().unwrap_or(today) };
+    let start = expected;
+    let mut count = 0;
+    for day in active.iter().skip_while(|day| **day > start) {
+        if *day != expected {
+            break;
+        }
+        count += 1;
+        expected = expected.pred_opt().unwrap_or(expected);
+    }
+    count
+}
+
+impl Database {
+    /// Focus seconds recorded so far today, in local time.
+    pub async fn get_today_focus_time(&self) -> Result<i64, sqlx::Error> {
+        let day_start_hour = self.day_start_hour().await?;
+        self.focus_time_today(Local::now(), day_start_hour).await
+    }
+
+    async fn focus_time_today<Tz: TimeZone>(&self, now: DateTime<Tz>, day_start_hour: u8) -> Result<i64, sqlx::Error> {
+        let tz = now.timezone();
+        let (from, until) = day_range(day_of(now.timestamp(), day_start_hour, &tz), day_start_hour, &tz);
+        let total: Option<i64> = sqlx::query_scalar(
+            "SELECT SUM(duration_seconds) FROM sessions WHERE start_time >= ? AND start_time < ?"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(total.unwrap_or(0))
+    }
+
+    /// Statistics for the `days` days up to and including today, in local time.
+    pub async fn focus_stats(&self, days: u32) -> Result<FocusStats, sqlx::Error> {
+        let day_start_hour = self.day_start_hour().await?;
+        self.focus_stats_at(days, Local::now(), day_start_hour).await
+    }
+
+    async fn focus_stats_at<Tz: TimeZone>(&self, days: u32, now: DateTime<Tz>, day_start_hour: u8) -> Result<FocusStats, sqlx::Error> {
+        let tz = now.timezone();
+        let today = day_of(now.timestamp(), day_start_hour, &tz);
+        let first = today.checked_sub_days(Days::new(days.saturating_sub(1) as u64)).unwrap_or(today);
+        let from = start_of_day(first, day_start_hour, &tz);
+        let (_, until) = day_range(today, day_start_hour, &tz);
+
+        let sessions: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT start_time, duration_seconds FROM sessions WHERE start_time >= ? AND start_time < ?"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let tasks = sqlx::query_as::<_, TaskTotal>(
+            "SELECT s.task_id, t.text, SUM(s.duration_seconds) AS seconds, COUNT(*) AS sessions
+             FROM sessions s LEFT JOIN tasks t ON t.id = s.task_id
+             WHERE s.start_time >= ? AND s.start_time < ?
+             GROUP BY s.task_id
+             ORDER BY seconds DESC, s.task_id"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let projects = sqlx::query_as::<_, ProjectTotal>(
+            "SELECT p.name AS project, p.color, SUM(s.duration_seconds) AS seconds, COUNT(*) AS sessions
+             FROM sessions s
+             LEFT JOIN tasks t ON t.id = s.task_id
+             LEFT JOIN projects p ON p.id = t.project_id
+             WHERE s.start_time >= ? AND s.start_time < ?
+             GROUP BY p.id
+             ORDER BY seconds DESC, p.name"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let starts: Vec<i64> = sqlx::query_scalar(
+            "SELECT start_time FROM sessions WHERE duration_seconds > 0 AND start_time < ? ORDER BY start_time DESC"
+        )
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut active: Vec<NaiveDate> = starts.into_iter().map(|start| day_of(start, day_start_hour, &tz)).collect();
+        active.dedup();
+
+        Ok(FocusStats {
+            days: day_totals(&sessions, first, today, day_start_hour, &tz),
+            hours: hour_totals(&sessions, &tz),
+            streak: streak(&active, today),
+            tasks,
+            projects,
+        })
+    }
+
+    /// Estimate accuracy for the `weeks` weeks up to and including this one,
+    /// oldest first, by the day estimated tasks were completed. Actual
+    /// pomodoros are the completed focus blocks attributed to each task.
+    pub async fn estimate_report(&self, weeks: u32) -> Result<Vec<EstimateWeek>, sqlx::Error> {
+        let day_start_hour = self.day_start_hour().await?;
+        self.estimate_report_at(weeks, Local::now(), day_start_hour).await
+    }
+
+    async fn estimate_report_at<Tz: TimeZone>(&self, weeks: u32, now: DateTime<Tz>, day_start_hour: u8) -> Result<Vec<EstimateWeek>, sqlx::Error> {
+        let tz = now.timezone();
+        let today = day_of(now.timestamp(), day_start_hour, &tz);
+        let first = week_start(today) - Days::new(7 * u64::from(weeks.saturating_sub(1)));
+        let from = start_of_day(first, day_start_hour, &tz);
+        let (_, until) = day_range(today, day_start_hour, &tz);
+
+        let finished: Vec<(i64, u32, u32)> = sqlx::query_as(
+            "SELECT t.completed_at, t.estimate,
+                    (SELECT COUNT(*) FROM sessions s WHERE s.task_id = t.id AND s.completed)
+             FROM tasks t
+             WHERE t.estimate IS NOT NULL AND t.completed_at >= ? AND t.completed_at < ?"
+        )
+        .bind(from)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(estimate_weeks(&finished, first, weeks, day_start_hour, &tz))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::FocusSession;
+    use chrono::FixedOffset;
+    use chrono_tz::America::{New_York, Sao_Paulo};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    async fn record(db: &Database, start: i64, seconds: i64, task_id: Option<i64>) {
+        let session = FocusSession {
+            task_id,
+            planned_seconds: 1500,
+            actual_seconds: seconds,
+            started_at: start,
+            ended_at: start + seconds,
+            completed: true,
+        };
+        db.add_session(&session).await.unwrap();
+    }
+
+    #[test]
+    fn days_start_at_the_day_start_hour() {
+        let noon = Sao_Paulo.with_ymd_and_hms(2024, 3, 5, 12, 0, 0).unwrap().timestamp();
+        let after_midnight = Sao_Paulo.with_ymd_and_hms(2024, 3, 6, 1, 30, 0).unwrap().timestamp();
+        assert_eq!(day_of(after_midnight, 0, &Sao_Paulo), date(2024, 3, 6));
+        assert_eq!(day_of(after_midnight, 4, &Sao_Paulo), date(2024, 3, 5));
+        assert_eq!(day_of(noon, 4, &Sao_Paulo), date(2024, 3, 5));
+
+        let four_am = Sao_Paulo.with_ymd_and_hms(2024, 3, 5, 4, 0, 0).unwrap().timestamp();
+        assert_eq!(day_range(date(2024, 3, 5), 4, &Sao_Paulo), (four_am, four_am + 24 * 3600));
+    }
+
+    #[test]
+    fn day_boundaries_follow_dst_changes() {
+        let hours = |tz: &chrono_tz::Tz, day: NaiveDate, day_start_hour: u8| {
+            let (from, until) = day_range(day, day_start_hour, tz);
+            // Whatever the boundary is, both sides of it must agree on the day
+            assert_eq!(day_of(from, day_start_hour, tz), day);
+            assert_eq!(day_of(from - 1, day_start_hour, tz), day.pred_opt().unwrap());
+            assert_eq!(day_of(until - 1, day_start_hour, tz), day);
+            (until - from) / 3600
+        };
+
+        // New York springs forward at 02:00 and falls back at 02:00
+        assert_eq!(hours(&New_York, date(2024, 3, 10), 0), 23);
+        assert_eq!(hours(&New_York, date(2024, 11, 3), 0), 25);
+        // 02:00 does not exist on the 10th, so that day starts at 03:00
+        assert_eq!(hours(&New_York, date(2024, 3, 10), 2), 23);
+        assert_eq!(
+            start_of_day(date(2024, 3, 10), 2, &New_York),
+            New_York.with_ymd_and_hms(2024, 3, 10, 3, 0, 0).unwrap().timestamp()
+        );
+        // 01:00 happens twice on November 3rd; the day starts at the first one
+        assert_eq!(hours(&New_York, date(2024, 11, 3), 1), 25);
+        assert_eq!(hours(&New_York, date(2024, 11, 4), 1), 24);
+
+        // São Paulo used to skip midnight itself when DST started
+        assert_eq!(hours(&Sao_Paulo, date(2018, 11, 4), 0), 23);
+        assert_eq!(
+            start_of_day(date(2018, 11, 4), 0, &Sao_Paulo),
+            Sao_Paulo.with_ymd_and_hms(2018, 11, 4, 1, 0, 0).unwrap().timestamp()
+        );
+        assert_eq!(hours(&Sao_Paulo, date(2019, 2, 16), 0), 25);
+    }
+
+    #[tokio::test]
+    async fn today_is_the_local_day() {
+        let db = crate::model::tests::memory_db().await;
+        let at = |d: u32, h: u32, m: u32| Sao_Paulo.with_ymd_and_hms(2024, 3, d, h, m, 0).unwrap().timestamp();
+        record(&db, at(4, 23, 30), 300, None).await;
+        record(&db, at(5, 8, 0), 600, None).await;
+        // Already the 6th in UTC
+        record(&db, at(5, 21, 30), 1500, None).await;
+        record(&db, at(6, 1, 0), 900, None).await;
+
+        let late_evening = Sao_Paulo.with_ymd_and_hms(2024, 3, 5, 23, 0, 0).unwrap();
+        assert_eq!(db.focus_time_today(late_evening, 0).await.unwrap(), 2100);
+
+        // With the day starting at 04:00, one o'clock still belongs to the 5th
+        let night = Sao_Paulo.with_ymd_and_hms(2024, 3, 6, 2, 0, 0).unwrap();
+        assert_eq!(db.focus_time_today(night, 0).await.unwrap(), 900);
+        assert_eq!(db.focus_time_today(night, 4).await.unwrap(), 3000);
+        let stats = db.focus_stats_at(2, night, 4).await.unwrap();
+        assert_eq!(stats.days, vec![(date(2024, 3, 4), 300), (date(2024, 3, 5), 3000)]);
+        assert_eq!(stats.streak, 2);
+    }
+
+    #[tokio::test]
+    async fn today_spans_a_dst_change() {
+        let db = crate::model::tests::memory_db().await;
+        let edt = FixedOffset::west_opt(4 * 3600).unwrap();
+        let est = FixedOffset::west_opt(5 * 3600).unwrap();
+        record(&db, edt.with_ymd_and_hms(2024, 11, 2, 23, 30, 0).unwrap().timestamp(), 300, None).await;
+        // 01:30 happens twice on November 3rd; both count
+        record(&db, edt.with_ymd_and_hms(2024, 11, 3, 1, 30, 0).unwrap().timestamp(), 600, None).await;
+        record(&db, est.with_ymd_and_hms(2024, 11, 3, 1, 30, 0).unwrap().timestamp(), 600, None).await;
+        record(&db, est.with_ymd_and_hms(2024, 11, 3, 23, 30, 0).unwrap().timestamp(), 1500, None).await;
+
+        let now = New_York.with_ymd_and_hms(2024, 11, 3, 23, 45, 0).unwrap();
+        assert_eq!(db.focus_time_today(now, 0).await.unwrap(), 2700);
+        let stats = db.focus_stats_at(2, now, 0).await.unwrap();
+        assert_eq!(stats.days, vec![(date(2024, 11, 2), 300), (date(2024, 11, 3), 2700)]);
+        assert_eq!((stats.hours[1], stats.hours[23]), (1200, 1800));
+    }
+
+    #[test]
+    fn buckets_days_in_the_given_time_zone() {
+        let sao_paulo = FixedOffset::west_opt(3 * 3600).unwrap();
+        // 2024-03-06 01:30 UTC is still the 5th in São Paulo
+        let late = sao_paulo.with_ymd_and_hms(2024, 3, 5, 22, 30, 0).unwrap().timestamp();
+        let morning = sao_paulo.with_ymd_and_hms(2024, 3, 6, 9, 0, 0).unwrap().timestamp();
+
+        let days = day_totals(&[(late, 600), (morning, 1500)], date(2024, 3, 4), date(2024, 3, 6), 0, &sao_paulo);
+        assert_eq!(days, vec![(date(2024, 3, 4), 0), (date(2024, 3, 5), 600), (date(2024, 3, 6), 1500)]);
+
+        let hours = hour_totals(&[(late, 600), (morning, 1500)], &sao_paulo);
+        assert_eq!((hours[22], hours[9], hours.iter().sum::<i64>()), (600, 1500, 2100));
+    }
+
+    #[test]
+    fn streak_may_start_yesterday() {
+        let today = date(2024, 3, 10);
+        assert_eq!(streak(&[date(2024, 3, 10), date(2024, 3, 9), date(2024, 3, 8), date(2024, 3, 6)], today), 3);
+        assert_eq!(streak(&[date(2024, 3, 9), date(2024, 3, 8)], today), 2);
+        assert_eq!(streak(&[date(2024, 3, 8)], today), 0);
+        assert_eq!(streak(&[], today), 0);
+    }
+
+    #[tokio::test]
+    async fn aggregates_sessions_from_the_database() {
+        let db = crate::model::tests::memory_db().await;
+        db.add_task("write report").await.unwrap();
+        let task_id = db.get_tasks().await.unwrap()[0].id;
+
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let now = utc.with_ymd_and_hms(2024, 3, 10, 18, 0, 0).unwrap();
+        let at = |d: u32, h: u32| utc.with_ymd_and_hms(2024, 3, d, h, 0, 0).unwrap().timestamp();
+        for (start, seconds, task_id) in [
+            (at(10, 9), 1500, Some(task_id)),
+            (at(10, 14), 1500, Some(task_id)),
+            (at(9, 9), 600, None),
+            (at(1, 9), 1500, Some(task_id)),
+        ] {
+            record(&db, start, seconds, task_id).await;
+        }
+
+        let stats = db.focus_stats_at(7, now, 0).await.unwrap();
+        assert_eq!(stats.days.len(), 7);
+        assert_eq!(stats.days[6], (date(2024, 3, 10), 3000));
+        assert_eq!(stats.days[5], (date(2024, 3, 9), 600));
+        assert_eq!((stats.hours[9], stats.hours[14]), (2100, 1500));
+        assert_eq!(stats.streak, 2);
+        assert_eq!(
+            stats.tasks.iter().map(|t| (t.label(), t.seconds, t.sessions)).collect::<Vec<_>>(),
+            vec![("write report", 3000, 2), ("No task", 600, 1)]
+        );
+
+        // The month view reaches the older session
+        assert_eq!(db.focus_stats_at(30, now, 0).await.unwrap().tasks[0].seconds, 4500);
+
+        let work = db.save_project("Work", None).await.unwrap();
+        db.set_task_project(task_id, Some(work.id)).await.unwrap();
+        let projects = db.focus_stats_at(7, now, 0).await.unwrap().projects;
+        assert_eq!(
+            projects.iter().map(|p| (p.label(), p.seconds, p.sessions)).collect::<Vec<_>>(),
+            vec![("Work", 3000, 2), ("No project", 600, 1)]
+        );
+        assert_eq!(projects[0].rgb(), Some(work.color));
+    }
+
+    #[tokio::test]
+    async fn compares_estimates_by_week_of_completion() {
+        let db = crate::model::tests::memory_db().await;
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let at = |m: u32, d: u32| utc.with_ymd_and_hms(2024, m, d, 12, 0, 0).unwrap().timestamp();
+        for (text, estimate, pomodoros, completed_at) in [
+            ("over", Some(2), 3, Some(at(3, 8))),
+            ("exact", Some(2), 2, Some(at(3, 10))),
+            ("skipped", Some(1), 0, Some(at(3, 1))),
+            ("unestimated", None, 4, Some(at(3, 9))),
+            ("open", Some(5), 1, None),
+        ] {
+            db.add_task(text).await.unwrap();
+            let mut task = db.get_tasks().await.unwrap().remove(0);
+            task.estimate = estimate;
+            db.update_task(&task).await.unwrap();
+            for _ in 0..pomodoros {
+                record(&db, at(3, 1), 1500, Some(task.id)).await;
+            }
+            sqlx::query("UPDATE tasks SET completed_at = ? WHERE id = ?")
+                .bind(completed_at)
+                .bind(task.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        // Sunday the 10th closes the week that started on Monday the 4th
+        let now = utc.with_ymd_and_hms(2024, 3, 10, 18, 0, 0).unwrap();
+        let report = db.estimate_report_at(2, now, 0).await.unwrap();
+        assert_eq!(report, vec![
+            EstimateWeek { start: date(2024, 2, 26), tasks: 1, estimated: 1, actual: 0, on_target: 0 },
+            EstimateWeek { start: date(2024, 3, 4), tasks: 2, estimated: 4, actual: 5, on_target: 1 },
+        ]);
+        assert_eq!(report[1].ratio(), Some(1.25));
+        let open = db.get_tasks().await.unwrap();
+        assert_eq!((open[0].estimate, open[0].pomodoros), (Some(5), 1));
+    }
+}
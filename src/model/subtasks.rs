@@ -0,0 +1,111 @@
+//! Checklist steps under a task. They live in their own table, deleted along
+//! with their task, and are loaded into [`Task::subtasks`] with the task.
+
+use super::{Database, Task};
+
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct Subtask {
+    pub id: i64,
+    pub task_id: i64,
+    pub text: String,
+    pub done: bool,
+}
+
+impl Task {
+    /// The first step not checked off yet.
+    pub fn next_step(&self) -> Option<&Subtask> {
+        self.subtasks.iter().find(|step| !step.done)
+    }
+
+    /// Steps checked off so far.
+    pub fn steps_done(&self) -> usize {
+        self.subtasks.iter().filter(|step| step.done).count()
+    }
+}
+
+impl Database {
+    /// Fills in the steps of each of `tasks`, in checklist order.
+    pub(super) async fn load_subtasks(&self, tasks: &mut [Task]) -> Result<(), sqlx::Error> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+        let ids = serde_json::to_string(&tasks.iter().map(|t| t.id).collect::<Vec<_>>())
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        let subtasks = sqlx::query_as::<_, Subtask>(
+            "SELECT id, task_id, text, done FROM subtasks
+             WHERE task_id IN (SELECT value FROM json_each(?))
+             ORDER BY position, id"
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for task in tasks.iter_mut() {
+            task.subtasks = subtasks.iter().filter(|step| step.task_id == task.id).cloned().collect();
+        }
+        Ok(())
+    }
+
+    /// Adds a step at the end of the task's checklist.
+    pub async fn add_subtask(&self, task_id: i64, text: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO subtasks (task_id, text, position)
+             VALUES (?1, ?2, (SELECT COALESCE(MAX(position), -1) + 1 FROM subtasks WHERE task_id = ?1))"
+        )
+        .bind(task_id)
+        .bind(text)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_subtask_done(&self, id: i64, done: bool) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE subtasks SET done = ? WHERE id = ?")
+            .bind(done)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_subtask(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM subtasks WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn steps_load_with_their_task_and_go_with_it() {
+        let db = crate::model::tests::memory_db().await;
+        db.add_task("other").await.unwrap();
+        db.add_task("write report").await.unwrap();
+        let (report, other) = {
+            let tasks = db.get_tasks().await.unwrap();
+            (tasks[0].id, tasks[1].id)
+        };
+        for step in ["outline", "draft", "proofread"] {
+            db.add_subtask(report, step).await.unwrap();
+        }
+        db.add_subtask(other, "unrelated").await.unwrap();
+
+        let task = db.get_task(report).await.unwrap().unwrap();
+        let steps: Vec<&str> = task.subtasks.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(steps, ["outline", "draft", "proofread"]);
+        assert_eq!(task.next_step().map(|s| s.text.as_str()), Some("outline"));
+
+        db.set_subtask_done(task.subtasks[0].id, true).await.unwrap();
+        db.delete_subtask(task.subtasks[1].id).await.unwrap();
+        let task = db.get_tasks().await.unwrap().remove(0);
+        assert_eq!((task.steps_done(), task.subtasks.len()), (1, 2));
+        assert_eq!(task.next_step().map(|s| s.text.as_str()), Some("proofread"));
+
+        db.delete_task(report).await.unwrap();
+        let left: Vec<String> = sqlx::query_scalar("SELECT text FROM subtasks").fetch_all(&db.pool).await.unwrap();
+        assert_eq!(left, ["unrelated"]);
+    }
+}
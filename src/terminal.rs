@@ -0,0 +1,416 @@
+//! Terminal input without external programs: termios raw mode, a decoder for
+//! the escape sequences terminals send, and a reader thread that turns stdin
+//! into [`Event`]s. When stdin is not a terminal (a pipe, a file, a test
+//! harness) input is read line by line instead, one key per character.
+
+use std::cell::Cell;
+use std::io::{self, BufRead, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Set by the signal handler; every loop checks it and unwinds normally, so
+/// the terminal is restored by the `Drop` guards on the way out.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Turns Ctrl-C, SIGTERM and SIGHUP into [`interrupted`] returning true.
+pub fn install_interrupt_handler() {
+    if let Err(e) = ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst)) {
+        eprintln!("Could not install Ctrl-C handler: {}", e);
+    }
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Columns and rows of the terminal on stdout.
+pub fn size() -> Option<(u16, u16)> {
+    #[cfg(unix)]
+    {
+        // SAFETY: TIOCGWINSZ fills the zeroed winsize
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        (ok && size.ws_col > 0 && size.ws_row > 0).then_some((size.ws_col, size.ws_row))
+    }
+
+    #[cfg(not(unix))]
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Delete,
+    Backspace,
+    Tab,
+    Enter,
+    Esc,
+    Char(char),
+    /// A character typed with Alt (or Esc followed quickly by a character).
+    Alt(char),
+    /// A control character other than Enter, Tab and Backspace, as its letter.
+    Ctrl(char),
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Key(Key),
+    /// The terminal window changed size.
+    Resize,
+}
+
+/// Result of decoding the start of an input buffer.
+#[derive(Debug, PartialEq)]
+pub enum Decoded {
+    /// A key and the number of bytes it used.
+    Key(Key, usize),
+    /// The bytes could be the start of a longer sequence; wait for more.
+    Incomplete,
+}
+
+const ESC: u8 = 0x1b;
+
+/// How long a lone Esc waits for the rest of a sequence before it counts as
+/// the Esc key. Terminals send a whole sequence in one write.
+const ESC_TIMEOUT: Duration = Duration::from_millis(30);
+
+/// Decodes the first key in `bytes`.
+pub fn decode(bytes: &[u8]) -> Decoded {
+    let Some(&first) = bytes.first() else {
+        return Decoded::Incomplete;
+    };
+    let key = match first {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        ESC => return decode_escape(bytes),
+        0x01..=0x1a => Key::Ctrl((b'a' + first - 1) as char),
+        0x00..=0x1f => Key::Unknown,
+        _ => return decode_char(bytes, Key::Char, 0),
+    };
+    Decoded::Key(key, 1)
+}
+
+/// Decodes a stalled buffer, i.e. one [`decode`] called incomplete but that
+/// got no more bytes within [`ESC_TIMEOUT`].
+pub fn decode_stalled(bytes: &[u8]) -> (Key, usize) {
+    match bytes {
+        [ESC] => (Key::Esc, 1),
+        // A truncated sequence; the introducer was most likely Alt+[ or Alt+O
+        [ESC, next, ..] => (Key::Alt(*next as char), 2),
+        _ => (Key::Unknown, 1),
+    }
+}
+
+fn decode_escape(bytes: &[u8]) -> Decoded {
+    match bytes.get(1) {
+        None => Decoded::Incomplete,
+        Some(b'[') => decode_csi(bytes),
+        Some(b'O') => match bytes.get(2) {
+            None => Decoded::Incomplete,
+            Some(&last) => Decoded::Key(final_key(last), 3),
+        },
+        // Esc pressed twice: the first one stands on its own
+        Some(&ESC) => Decoded::Key(Key::Esc, 1),
+        Some(_) => decode_char(bytes, Key::Alt, 1),
+    }
+}
+
+/// `ESC [ params final`, e.g. `ESC[A` or `ESC[1;5C` (Ctrl+Right, reported
+/// as plain Right) or `ESC[3~`.
+fn decode_csi(bytes: &[u8]) -> Decoded {
+    let params_end = 2 + bytes[2..].iter().take_while(|b| (0x30..=0x3f).contains(*b)).count();
+    let Some(&last) = bytes.get(params_end) else {
+        return Decoded::Incomplete;
+    };
+    let len = params_end + 1;
+    if !(0x40..=0x7e).contains(&last) {
+        return Decoded::Key(Key::Unknown, len);
+    }
+
+    let key = if last == b'~' {
+        let params = std::str::from_utf8(&bytes[2..params_end]).unwrap_or("");
+        match params.split(';').next().unwrap_or("") {
+            "1" | "7" => Key::Home,
+            "4" | "8" => Key::End,
+            "3" => Key::Delete,
+            "5" => Key::PageUp,
+            "6" => Key::PageDown,
+            _ => Key::Unknown,
+        }
+    } else {
+        final_key(last)
+    };
+    Decoded::Key(key, len)
+}
+
+fn final_key(last: u8) -> Key {
+    match last {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        _ => Key::Unknown,
+    }
+}
+
+/// Decodes the UTF-8 character starting at `bytes[offset]`.
+fn decode_char(bytes: &[u8], wrap: fn(char) -> Key, offset: usize) -> Decoded {
+    let len = match bytes[offset] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Decoded::Key(Key::Unknown, offset + 1),
+    };
+    let Some(encoded) = bytes.get(offset..offset + len) else {
+        return Decoded::Incomplete;
+    };
+    match std::str::from_utf8(encoded).ok().and_then(|s| s.chars().next()) {
+        Some(c) => Decoded::Key(wrap(c), offset + len),
+        None => Decoded::Key(Key::Unknown, offset + 1),
+    }
+}
+
+/// Keeps stdin in raw mode (no line buffering, no echo) until dropped.
+/// Ctrl-C and friends still raise signals.
+pub struct RawMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        if !io::stdin().is_terminal() {
+            return Err(io::Error::other("stdin is not a terminal"));
+        }
+
+        #[cfg(unix)]
+        {
+            // SAFETY: tcgetattr fills the zeroed struct, tcsetattr only reads it
+            unsafe {
+                let mut original: libc::termios = std::mem::zeroed();
+                if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let mut raw = original;
+                raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::IEXTEN);
+                raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+                raw.c_cc[libc::VMIN] = 1;
+                raw.c_cc[libc::VTIME] = 0;
+                if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(RawMode { original })
+            }
+        }
+
+        #[cfg(not(unix))]
+        Err(io::Error::new(io::ErrorKind::Unsupported, "raw mode needs a Unix terminal"))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restores the settings read in `enable`
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Input events read on a background thread, so callers can keep redrawing
+/// while nothing is typed.
+pub struct Input {
+    rx: Receiver<Event>,
+    closed: Cell<bool>,
+}
+
+impl Input {
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::channel();
+        if cfg!(unix) && io::stdin().is_terminal() {
+            thread::spawn(move || read_terminal(tx));
+        } else {
+            thread::spawn(move || read_lines(tx));
+        }
+        Input { rx, closed: Cell::new(false) }
+    }
+
+    /// The next event if one arrives within `timeout`. Once input has ended
+    /// this just waits out the timeout.
+    pub fn poll(&self, timeout: Duration) -> Option<Event> {
+        if !self.closed.get() {
+            match self.rx.recv_timeout(timeout) {
+                Ok(event) => return Some(event),
+                Err(RecvTimeoutError::Timeout) => return None,
+                Err(RecvTimeoutError::Disconnected) => self.closed.set(true),
+            }
+        }
+        thread::sleep(timeout);
+        None
+    }
+
+    /// Whether stdin was closed, so no more events will come.
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+}
+
+/// Non-terminal input: an empty line is Enter, anything else one key per
+/// character, so `printf 'q\n' | pomimi timer` works.
+fn read_lines(tx: Sender<Event>) {
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        let keys: Vec<Key> = if line.trim().is_empty() {
+            vec![Key::Enter]
+        } else {
+            line.trim().chars().map(Key::Char).collect()
+        };
+        for key in keys {
+            if tx.send(Event::Key(key)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_resize(_signal: libc::c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+fn read_terminal(tx: Sender<Event>) {
+    // SAFETY: the handler only stores to an atomic
+    unsafe {
+        libc::signal(libc::SIGWINCH, on_resize as *const () as libc::sighandler_t);
+    }
+
+    let mut pending: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 64];
+    loop {
+        // Wake up regularly to report resizes, quickly while a sequence is half read
+        let timeout = if pending.is_empty() { Duration::from_millis(100) } else { ESC_TIMEOUT };
+        let mut fds = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+        // SAFETY: `fds` is a single valid pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut fds, 1, timeout.as_millis() as libc::c_int) } > 0;
+
+        let mut events = Vec::new();
+        if RESIZED.swap(false, Ordering::SeqCst) {
+            events.push(Event::Resize);
+        }
+
+        if ready {
+            // Straight from the file descriptor: std's buffered stdin could hold
+            // bytes back that poll would then never report
+            // SAFETY: reads at most `buffer.len()` bytes into `buffer`
+            let read = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+            match read {
+                n if n > 0 => pending.extend_from_slice(&buffer[..n as usize]),
+                0 => return,
+                _ if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {}
+                _ => return,
+            }
+        } else if !pending.is_empty() {
+            let (key, len) = decode_stalled(&pending);
+            pending.drain(..len);
+            events.push(Event::Key(key));
+        }
+
+        while let Decoded::Key(key, len) = decode(&pending) {
+            pending.drain(..len);
+            events.push(Event::Key(key));
+        }
+
+        for event in events {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn read_terminal(tx: Sender<Event>) {
+    read_lines(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(mut bytes: &[u8]) -> Vec<Key> {
+        let mut keys = Vec::new();
+        while !bytes.is_empty() {
+            let (key, len) = match decode(bytes) {
+                Decoded::Key(key, len) => (key, len),
+                Decoded::Incomplete => decode_stalled(bytes),
+            };
+            keys.push(key);
+            bytes = &bytes[len..];
+        }
+        keys
+    }
+
+    #[test]
+    fn decodes_plain_and_control_keys() {
+        assert_eq!(
+            keys(b"a \r\n\t\x7f\x03"),
+            [Key::Char('a'), Key::Char(' '), Key::Enter, Key::Enter, Key::Tab, Key::Backspace, Key::Ctrl('c')]
+        );
+        assert_eq!(keys("é✓".as_bytes()), [Key::Char('é'), Key::Char('✓')]);
+    }
+
+    #[test]
+    fn decodes_arrows_in_both_modes() {
+        assert_eq!(keys(b"\x1b[A\x1b[B\x1b[C\x1b[D"), [Key::Up, Key::Down, Key::Right, Key::Left]);
+        assert_eq!(keys(b"\x1bOA\x1bOD"), [Key::Up, Key::Left]);
+        // Modifiers are ignored
+        assert_eq!(keys(b"\x1b[1;5C"), [Key::Right]);
+    }
+
+    #[test]
+    fn decodes_home_end_and_friends() {
+        assert_eq!(keys(b"\x1b[H\x1b[F\x1bOH\x1bOF"), [Key::Home, Key::End, Key::Home, Key::End]);
+        assert_eq!(keys(b"\x1b[1~\x1b[4~\x1b[7~\x1b[8~"), [Key::Home, Key::End, Key::Home, Key::End]);
+        assert_eq!(keys(b"\x1b[3~\x1b[5~\x1b[6~"), [Key::Delete, Key::PageUp, Key::PageDown]);
+        assert_eq!(keys(b"\x1b[99~\x1b[Z"), [Key::Unknown, Key::Unknown]);
+    }
+
+    #[test]
+    fn tells_esc_from_alt_sequences() {
+        assert_eq!(decode(b"\x1b"), Decoded::Incomplete);
+        assert_eq!(decode_stalled(b"\x1b"), (Key::Esc, 1));
+        assert_eq!(keys(b"\x1bj"), [Key::Alt('j')]);
+        assert_eq!(keys(b"\x1b\x1bj"), [Key::Esc, Key::Alt('j')]);
+        assert_eq!(keys("\x1bé".as_bytes()), [Key::Alt('é')]);
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_sequence() {
+        assert_eq!(decode(b"\x1b["), Decoded::Incomplete);
+        assert_eq!(decode(b"\x1b[1;5"), Decoded::Incomplete);
+        assert_eq!(decode(b"\x1bO"), Decoded::Incomplete);
+        assert_eq!(decode(&"✓".as_bytes()[..2]), Decoded::Incomplete);
+        assert_eq!(decode_stalled(b"\x1b["), (Key::Alt('['), 2));
+    }
+}
@@ -0,0 +1,577 @@
+//! UI-independent Pomodoro state machine shared by the GUI and the CLI.
+//!
+//! The timer never counts ticks: every query takes the current wall-clock
+//! time and derives the remaining time from when the phase started, minus
+//! pauses. Front-ends call [`Timer::tick`] as often as they like and react to
+//! the returned [`TimerEvent`]s.
+
+use crate::model::{FocusSession, TimerProfile, MIN_PHASE_SECS};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn duration_secs(&self, profile: &TimerProfile) -> u64 {
+        match self {
+            Phase::Focus => profile.focus_secs,
+            Phase::ShortBreak => profile.short_break_secs,
+            Phase::LongBreak => profile.long_break_secs,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Phase::Focus => "Focus",
+            Phase::ShortBreak => "Short Break",
+            Phase::LongBreak => "Long Break",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimerEvent {
+    /// A phase ended, either by running out or by being skipped or reset.
+    PhaseEnded {
+        phase: Phase,
+        started_at: SystemTime,
+        ended_at: SystemTime,
+        planned: Duration,
+        /// Time actually spent in the phase, pauses excluded.
+        actual: Duration,
+        /// `false` when the phase was cut short.
+        completed: bool,
+        /// Task that was selected when the phase started.
+        task_id: Option<i64>,
+    },
+    /// The next phase is ready; `running` is false when it waits for a start.
+    PhaseStarted { phase: Phase, running: bool },
+}
+
+impl TimerEvent {
+    /// The session to store for this event: only focus blocks with time spent
+    /// in them are recorded, by the GUI and the CLI alike.
+    pub fn focus_session(&self) -> Option<FocusSession> {
+        let TimerEvent::PhaseEnded { phase: Phase::Focus, started_at, ended_at, planned, actual, completed, task_id } = self else {
+            return None;
+        };
+        if actual.as_secs() == 0 {
+            return None;
+        }
+        Some(FocusSession {
+            task_id: *task_id,
+            planned_seconds: planned.as_secs() as i64,
+            actual_seconds: actual.as_secs() as i64,
+            started_at: unix_secs(*started_at),
+            ended_at: unix_secs(*ended_at),
+            completed: *completed,
+        })
+    }
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+}
+
+#[derive(Debug, Clone)]
+pub struct Timer {
+    profile: TimerProfile,
+    phase: Phase,
+    /// When the current phase started; `None` until it is started.
+    started_at: Option<SystemTime>,
+    /// Set while paused.
+    paused_at: Option<SystemTime>,
+    /// Time spent paused since `started_at`, excluding a pause still in progress.
+    paused_total: Duration,
+    cycles_completed: usize,
+    /// Seconds added to (or taken from) the current phase with [`Timer::extend`].
+    extra_secs: i64,
+    /// Whether the next phase starts on its own when one runs out.
+    auto_continue: bool,
+    /// Task the next phase to start is credited to.
+    task_id: Option<i64>,
+    /// Task the current phase is credited to, taken from `task_id` when it started.
+    phase_task_id: Option<i64>,
+}
+
+impl Timer {
+    pub fn new(profile: TimerProfile) -> Self {
+        Self {
+            profile,
+            phase: Phase::Focus,
+            started_at: None,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            cycles_completed: 0,
+            extra_secs: 0,
+            auto_continue: true,
+            task_id: None,
+            phase_task_id: None,
+        }
+    }
+
+    pub fn with_auto_continue(mut self, auto_continue: bool) -> Self {
+        self.auto_continue = auto_continue;
+        self
+    }
+
+    pub fn with_task(mut self, task_id: Option<i64>) -> Self {
+        self.set_task(task_id);
+        self
+    }
+
+    /// Selects the task phases are credited to from the next one that starts;
+    /// a phase already under way stays with the task it was started for.
+    pub fn set_task(&mut self, task_id: Option<i64>) {
+        self.task_id = task_id;
+    }
+
+    pub fn profile(&self) -> &TimerProfile {
+        &self.profile
+    }
+
+    /// Swaps the phase lengths in place; a running phase keeps its progress.
+    pub fn set_profile(&mut self, profile: TimerProfile) {
+        self.profile = profile;
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn cycles_completed(&self) -> usize {
+        self.cycles_completed
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some() && self.paused_at.is_none()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn total(&self) -> Duration {
+        let base = self.phase.duration_secs(&self.profile) as i64;
+        Duration::from_secs((base + self.extra_secs).max(0) as u64)
+    }
+
+    /// Lengthens the current phase by `secs`, or shortens it when negative,
+    /// never below a minute (or its own length, if shorter). Shortening past
+    /// the elapsed time ends the phase on the next [`Timer::tick`].
+    pub fn extend(&mut self, secs: i64) {
+        let base = self.phase.duration_secs(&self.profile) as i64;
+        self.extra_secs = (self.extra_secs + secs).max((MIN_PHASE_SECS as i64 - base).min(0));
+    }
+
+    /// Time spent in the current phase, pauses excluded.
+    pub fn elapsed(&self, now: SystemTime) -> Duration {
+        let Some(started_at) = self.started_at else {
+            return Duration::ZERO;
+        };
+        let until = self.paused_at.unwrap_or(now);
+        until.duration_since(started_at).unwrap_or_default().saturating_sub(self.paused_total)
+    }
+
+    pub fn remaining(&self, now: SystemTime) -> Duration {
+        self.total().saturating_sub(self.elapsed(now))
+    }
+
+    /// The instant the current phase runs out, if it is running.
+    pub fn deadline(&self) -> Option<SystemTime> {
+        if !self.is_running() {
+            return None;
+        }
+        Some(self.started_at? + self.paused_total + self.total())
+    }
+
+    /// Starts a waiting phase or resumes a paused one.
+    pub fn start(&mut self, now: SystemTime) {
+        match (self.started_at, self.paused_at) {
+            (None, _) => {
+                self.started_at = Some(now);
+                self.phase_task_id = self.task_id;
+            }
+            (Some(_), Some(paused_at)) => {
+                self.paused_total += now.duration_since(paused_at).unwrap_or_default();
+                self.paused_at = None;
+            }
+            (Some(_), None) => {}
+        }
+    }
+
+    pub fn pause(&mut self, now: SystemTime) {
+        if self.is_running() {
+            self.paused_at = Some(now);
+        }
+    }
+
+    pub fn resume(&mut self, now: SystemTime) {
+        if self.is_paused() {
+            self.start(now);
+        }
+    }
+
+    pub fn toggle(&mut self, now: SystemTime) {
+        if self.is_running() {
+            self.pause(now);
+        } else if self.is_paused() {
+            self.resume(now);
+        } else {
+            self.start(now);
+        }
+    }
+
+    /// Moves the timer through every phase boundary that passed before
+    /// `now` (several, after a suspended laptop wakes up).
+    pub fn tick(&mut self, now: SystemTime) -> Vec<TimerEvent> {
+        let mut events = Vec::new();
+        while let Some(deadline) = self.deadline().filter(|deadline| *deadline <= now) {
+            events.push(self.end_phase(deadline, true));
+            let running = self.auto_continue;
+            self.advance(deadline, running);
+            events.push(TimerEvent::PhaseStarted { phase: self.phase, running });
+        }
+        events
+    }
+
+    /// Ends the current phase early and moves to the next one, which keeps
+    /// running if the skipped one was.
+    pub fn skip(&mut self, now: SystemTime) -> Vec<TimerEvent> {
+        let running = self.is_running();
+        let mut events = self.tick(now);
+        if self.is_started() {
+            events.push(self.end_phase(now, false));
+        }
+        self.advance(now, running);
+        events.push(TimerEvent::PhaseStarted { phase: self.phase, running });
+        events
+    }
+
+    /// Goes back to a fresh, stopped focus phase and clears the cycle count.
+    pub fn reset(&mut self, now: SystemTime) -> Vec<TimerEvent> {
+        let mut events = self.tick(now);
+        if self.is_started() {
+            events.push(self.end_phase(now, false));
+        }
+        *self = Timer::new(self.profile.clone())
+            .with_auto_continue(self.auto_continue)
+            .with_task(self.task_id);
+        events
+    }
+
+    fn end_phase(&self, ended_at: SystemTime, completed: bool) -> TimerEvent {
+        TimerEvent::PhaseEnded {
+            phase: self.phase,
+            started_at: self.started_at.unwrap_or(ended_at),
+            ended_at,
+            planned: self.total(),
+            actual: self.elapsed(ended_at).min(self.total()),
+            completed,
+            task_id: self.phase_task_id,
+        }
+    }
+
+    fn advance(&mut self, at: SystemTime, running: bool) {
+        self.phase = match self.phase {
+            Phase::Focus => {
+                self.cycles_completed += 1;
+                if self.cycles_completed.is_multiple_of(self.profile.cycles_before_long_break.max(1)) {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Focus,
+        };
+        self.started_at = if running { Some(at) } else { None };
+        self.phase_task_id = self.task_id;
+        self.paused_at = None;
+        self.paused_total = Duration::ZERO;
+        self.extra_secs = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> TimerProfile {
+        TimerProfile {
+            name: "Test".to_string(),
+            focus_secs: 25 * 60,
+            short_break_secs: 5 * 60,
+            long_break_secs: 30 * 60,
+            cycles_before_long_break: 4,
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + secs)
+    }
+
+    fn phases_started(events: &[TimerEvent]) -> Vec<Phase> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                TimerEvent::PhaseStarted { phase, .. } => Some(*phase),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn does_not_run_until_started() {
+        let mut timer = Timer::new(profile());
+        assert!(timer.tick(at(10_000)).is_empty());
+        assert_eq!(timer.remaining(at(10_000)), Duration::from_secs(25 * 60));
+    }
+
+    #[test]
+    fn focus_is_followed_by_short_break_then_focus() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+
+        let events = timer.tick(at(25 * 60));
+        assert_eq!(phases_started(&events), vec![Phase::ShortBreak]);
+        assert!(matches!(
+            events[0],
+            TimerEvent::PhaseEnded { phase: Phase::Focus, completed: true, .. }
+        ));
+
+        let events = timer.tick(at(30 * 60));
+        assert_eq!(phases_started(&events), vec![Phase::Focus]);
+        assert_eq!(timer.remaining(at(30 * 60)), Duration::from_secs(25 * 60));
+    }
+
+    #[test]
+    fn long_break_comes_after_configured_cycles() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+
+        // Three full focus + short break rounds, then the fourth focus
+        let events = timer.tick(at(3 * 30 * 60 + 25 * 60));
+        assert_eq!(
+            phases_started(&events),
+            vec![
+                Phase::ShortBreak, Phase::Focus,
+                Phase::ShortBreak, Phase::Focus,
+                Phase::ShortBreak, Phase::Focus,
+                Phase::LongBreak,
+            ]
+        );
+        assert_eq!(timer.cycles_completed(), 4);
+    }
+
+    #[test]
+    fn catches_up_across_missed_boundaries_without_drift() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+
+        // Asleep through the first focus and break, woke 10s into the next focus
+        let events = timer.tick(at(30 * 60 + 10));
+        assert_eq!(phases_started(&events), vec![Phase::ShortBreak, Phase::Focus]);
+        assert_eq!(timer.phase(), Phase::Focus);
+        assert_eq!(timer.remaining(at(30 * 60 + 10)), Duration::from_secs(25 * 60 - 10));
+
+        let ends: Vec<SystemTime> = events
+            .iter()
+            .filter_map(|event| match event {
+                TimerEvent::PhaseEnded { ended_at, .. } => Some(*ended_at),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ends, vec![at(25 * 60), at(30 * 60)]);
+    }
+
+    #[test]
+    fn pauses_are_excluded_from_elapsed_time() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        timer.pause(at(60));
+        assert_eq!(timer.elapsed(at(600)), Duration::from_secs(60));
+        assert!(timer.tick(at(10_000)).is_empty());
+
+        timer.resume(at(10_000));
+        assert_eq!(timer.elapsed(at(10_030)), Duration::from_secs(90));
+
+        timer.pause(at(10_100));
+        timer.resume(at(10_200));
+        assert_eq!(timer.remaining(at(10_200)), Duration::from_secs(25 * 60 - 160));
+
+        let deadline = at(10_200 + 25 * 60 - 160);
+        assert_eq!(timer.deadline(), Some(deadline));
+        match &timer.tick(deadline)[0] {
+            TimerEvent::PhaseEnded { actual, .. } => assert_eq!(*actual, Duration::from_secs(25 * 60)),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn remaining_time_follows_the_clock_not_the_ticks() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        assert_eq!(timer.deadline(), Some(at(25 * 60)));
+
+        // Late and irregular ticks neither lose nor gain time
+        for secs in [1, 2, 9, 600, 601] {
+            assert!(timer.tick(at(secs)).is_empty());
+            assert_eq!(timer.remaining(at(secs)), Duration::from_secs(25 * 60 - secs));
+        }
+        assert_eq!(timer.deadline(), Some(at(25 * 60)));
+
+        // A clock set back before the start counts as no time spent
+        assert_eq!(timer.elapsed(SystemTime::UNIX_EPOCH), Duration::ZERO);
+    }
+
+    #[test]
+    fn pauses_push_the_deadline_back_by_their_length() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        timer.pause(at(100));
+        assert_eq!(timer.deadline(), None);
+
+        // Pausing again keeps the first pause instant
+        timer.pause(at(200));
+        timer.resume(at(400));
+        assert_eq!(timer.deadline(), Some(at(25 * 60 + 300)));
+
+        // Waking up long after, the boundaries land where the pause put them
+        let events = timer.tick(at(2 * 60 * 60));
+        let ends: Vec<SystemTime> = events
+            .iter()
+            .filter_map(|event| match event {
+                TimerEvent::PhaseEnded { ended_at, .. } => Some(*ended_at),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ends[..2], [at(25 * 60 + 300), at(30 * 60 + 300)]);
+        assert_eq!(timer.deadline().unwrap().duration_since(at(2 * 60 * 60)).unwrap(), timer.remaining(at(2 * 60 * 60)));
+    }
+
+    #[test]
+    fn skip_ends_phase_early_and_keeps_running() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+
+        let events = timer.skip(at(100));
+        assert!(matches!(
+            events[0],
+            TimerEvent::PhaseEnded { phase: Phase::Focus, completed: false, actual, .. } if actual == Duration::from_secs(100)
+        ));
+        assert_eq!(timer.phase(), Phase::ShortBreak);
+        assert!(timer.is_running());
+        assert_eq!(timer.cycles_completed(), 1);
+    }
+
+    #[test]
+    fn reset_returns_to_fresh_focus() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        timer.tick(at(25 * 60 + 10));
+
+        let events = timer.reset(at(25 * 60 + 20));
+        assert!(matches!(events[0], TimerEvent::PhaseEnded { phase: Phase::ShortBreak, completed: false, .. }));
+        assert_eq!(timer.phase(), Phase::Focus);
+        assert_eq!(timer.cycles_completed(), 0);
+        assert!(!timer.is_started());
+    }
+
+    #[test]
+    fn waits_between_phases_without_auto_continue() {
+        let mut timer = Timer::new(profile()).with_auto_continue(false);
+        timer.start(at(0));
+
+        let events = timer.tick(at(40 * 60));
+        assert_eq!(events.last(), Some(&TimerEvent::PhaseStarted { phase: Phase::ShortBreak, running: false }));
+        assert!(!timer.is_started());
+
+        timer.start(at(41 * 60));
+        assert_eq!(timer.remaining(at(42 * 60)), Duration::from_secs(4 * 60));
+    }
+
+    #[test]
+    fn only_focus_time_becomes_a_session() {
+        let mut timer = Timer::new(profile()).with_task(Some(7));
+        timer.start(at(0));
+        let events = timer.reset(at(600));
+        let session = events[0].focus_session().unwrap();
+        assert_eq!(session.task_id, Some(7));
+        assert_eq!(session.actual_seconds, 600);
+        assert_eq!(session.planned_seconds, 25 * 60);
+        assert_eq!(session.ended_at - session.started_at, 600);
+        assert!(!session.completed);
+
+        let mut timer = Timer::new(profile()).with_auto_continue(false);
+        timer.start(at(0));
+        let breaks = timer.tick(at(30 * 60));
+        timer.start(at(30 * 60));
+        let events = timer.skip(at(31 * 60));
+        assert!(breaks[0].focus_session().unwrap().completed);
+        assert!(events.iter().all(|event| event.focus_session().is_none()));
+
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        assert!(timer.reset(at(0))[0].focus_session().is_none());
+    }
+
+    #[test]
+    fn focus_blocks_stay_with_the_task_they_started_with() {
+        let mut timer = Timer::new(profile()).with_task(Some(1));
+        timer.start(at(0));
+        timer.set_task(Some(2));
+
+        let events = timer.tick(at(30 * 60));
+        assert_eq!(events[0].focus_session().unwrap().task_id, Some(1));
+        assert!(matches!(events[2], TimerEvent::PhaseEnded { task_id: Some(2), .. }));
+
+        timer.set_task(None);
+        let events = timer.reset(at(31 * 60));
+        assert_eq!(events[0].focus_session().unwrap().task_id, Some(2));
+
+        timer.start(at(32 * 60));
+        assert_eq!(timer.reset(at(33 * 60))[0].focus_session().unwrap().task_id, None);
+    }
+
+    #[test]
+    fn extending_moves_the_deadline_of_the_current_phase_only() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        timer.extend(5 * 60);
+        assert_eq!(timer.total(), Duration::from_secs(30 * 60));
+        assert!(timer.tick(at(25 * 60)).is_empty());
+
+        let events = timer.tick(at(30 * 60));
+        assert!(matches!(events[0], TimerEvent::PhaseEnded { planned, .. } if planned == Duration::from_secs(30 * 60)));
+        assert_eq!(timer.total(), Duration::from_secs(5 * 60));
+    }
+
+    #[test]
+    fn shortening_stops_at_a_minute_and_can_end_the_phase() {
+        let mut timer = Timer::new(profile());
+        timer.start(at(0));
+        timer.extend(-60 * 60);
+        assert_eq!(timer.total(), Duration::from_secs(60));
+
+        let events = timer.tick(at(2 * 60));
+        assert!(matches!(events[0], TimerEvent::PhaseEnded { phase: Phase::Focus, completed: true, .. }));
+    }
+
+    #[test]
+    fn short_phases_are_not_stretched_to_a_minute() {
+        let mut timer = Timer::new(TimerProfile { focus_secs: 30, ..profile() });
+        assert_eq!(timer.total(), Duration::from_secs(30));
+        timer.extend(-60);
+        assert_eq!(timer.total(), Duration::from_secs(30));
+        timer.extend(60);
+        assert_eq!(timer.total(), Duration::from_secs(90));
+    }
+}
@@ -0,0 +1,578 @@
+//! Full-screen terminal interface laid out like the GUI: the big timer, the
+//! priority task list with the active task, and today's focus total.
+//!
+//! Every pass builds the whole frame as lines of text and compares it with
+//! what is on screen, so only the rows that changed get rewritten.
+
+use crate::alerts::Alerts;
+use crate::model::{Database, Task, TimerProfile};
+use crate::terminal::{self, install_interrupt_handler, interrupted, Event, Input, Key, RawMode};
+use crate::timer::{Phase, Timer, TimerEvent};
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// `pomimi tui`
+pub fn run() -> Result<(), String> {
+    let _raw = RawMode::enable().map_err(|_| "the TUI needs a terminal".to_string())?;
+    let mut app = App::load()?;
+    install_interrupt_handler();
+    let input = Input::spawn();
+    let mut screen = Screen::enter();
+
+    while !interrupted() {
+        let now = SystemTime::now();
+        let events = app.timer.tick(now);
+        app.handle_timer_events(events);
+        screen.draw(app.frame(now, screen.size));
+
+        match input.poll(Duration::from_millis(200)) {
+            Some(Event::Key(key)) if !app.handle_key(key) => break,
+            Some(Event::Resize) => screen.resize(),
+            _ => {}
+        }
+    }
+
+    // Keep whatever was focused so far as an unfinished session
+    let events = app.timer.reset(SystemTime::now());
+    app.handle_timer_events(events);
+    Ok(())
+}
+
+struct App {
+    runtime: tokio::runtime::Runtime,
+    db: Database,
+    alerts: Alerts,
+    accent: [u8; 3],
+    timer: Timer,
+    profiles: Vec<TimerProfile>,
+    selected_profile: usize,
+    tasks: Vec<Task>,
+    /// Row under the cursor in the task list.
+    selection: usize,
+    active_task_id: Option<i64>,
+    undo_task_id: Option<i64>,
+    today_secs: i64,
+    /// Text typed so far while adding a task.
+    new_task: Option<String>,
+    message: Option<String>,
+}
+
+impl App {
+    fn load() -> Result<Self, String> {
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("could not start runtime: {}", e))?;
+        let (db, profiles, settings, tasks, today_secs) = runtime
+            .block_on(async {
+                let db = Database::new().await?;
+                let profiles = db.get_profiles().await?;
+                let settings = db.load_settings().await?;
+                let tasks = db.get_tasks().await?;
+                let today = db.get_today_focus_time().await?;
+                Ok::<_, sqlx::Error>((db, profiles, settings, tasks, today))
+            })
+            .map_err(|e| format!("could not open database: {}", e))?;
+
+        let selected_profile = settings
+            .selected_profile
+            .as_ref()
+            .and_then(|name| profiles.iter().position(|p| &p.name == name))
+            .unwrap_or(0);
+        let mut alerts = Alerts::system();
+        alerts.configure(&settings);
+
+        Ok(App {
+            runtime,
+            db,
+            alerts,
+            accent: settings.accent_color,
+            timer: Timer::new(profiles[selected_profile].clone()),
+            profiles,
+            selected_profile,
+            tasks,
+            selection: 0,
+            active_task_id: None,
+            undo_task_id: None,
+            today_secs,
+            new_task: None,
+            message: None,
+        })
+    }
+
+    fn accent(&self) -> String {
+        let [r, g, b] = self.accent;
+        format!("\x1b[38;2;{};{};{}m", r, g, b)
+    }
+
+    fn reload_tasks(&mut self) {
+        match self.runtime.block_on(self.db.get_tasks()) {
+            Ok(tasks) => self.tasks = tasks,
+            Err(e) => self.message = Some(format!("Could not load tasks: {}", e)),
+        }
+        self.selection = self.selection.min(self.tasks.len().saturating_sub(1));
+    }
+
+    /// Runs a task operation and reloads the list, reporting failures.
+    fn task_operation<F: std::future::Future<Output = Result<(), sqlx::Error>>>(&mut self, operation: F) {
+        if let Err(e) = self.runtime.block_on(operation) {
+            self.message = Some(format!("Task operation failed: {}", e));
+        }
+        self.reload_tasks();
+    }
+
+    fn selected_task(&self) -> Option<&Task> {
+        self.tasks.get(self.selection)
+    }
+
+    /// Makes `id` the active task; a focus block already running keeps
+    /// counting towards the task it started with.
+    fn select_task(&mut self, id: Option<i64>) {
+        self.active_task_id = id;
+        self.timer.set_task(id);
+    }
+
+    fn handle_timer_events(&mut self, events: Vec<TimerEvent>) {
+        for event in events {
+            if let TimerEvent::PhaseEnded { phase, completed: true, .. } = event {
+                self.alerts.phase_ended(phase);
+            }
+            let Some(session) = event.focus_session() else {
+                continue;
+            };
+            let recorded = self.runtime.block_on(async {
+                self.db.add_session(&session).await?;
+                self.db.get_today_focus_time().await
+            });
+            match recorded {
+                Ok(today) => self.today_secs = today,
+                Err(e) => self.message = Some(format!("Could not record session: {}", e)),
+            }
+        }
+    }
+
+    /// Returns `false` when the user quits.
+    fn handle_key(&mut self, key: Key) -> bool {
+        if self.new_task.is_some() {
+            self.handle_input_key(key);
+            return true;
+        }
+
+        self.message = None;
+        let now = SystemTime::now();
+        match key {
+            Key::Char('q') | Key::Esc => return false,
+            Key::Char(' ') => self.timer.toggle(now),
+            Key::Char('s') => {
+                let events = self.timer.skip(now);
+                self.handle_timer_events(events);
+            }
+            Key::Char('r') => {
+                let events = self.timer.reset(now);
+                self.handle_timer_events(events);
+            }
+            Key::Char('+') | Key::Char('=') => self.timer.extend(60),
+            Key::Char('-') => self.timer.extend(-60),
+            Key::Char('p') => self.next_profile(),
+            Key::Char('j') | Key::Down => {
+                self.selection = (self.selection + 1).min(self.tasks.len().saturating_sub(1));
+            }
+            Key::Char('k') | Key::Up => self.selection = self.selection.saturating_sub(1),
+            Key::Char('g') | Key::Home => self.selection = 0,
+            Key::Char('G') | Key::End => self.selection = self.tasks.len().saturating_sub(1),
+            Key::Char('J') => self.move_selected(1),
+            Key::Char('K') => self.move_selected(-1),
+            Key::Enter => {
+                if let Some(id) = self.selected_task().map(|t| t.id) {
+                    self.select_task(if self.active_task_id == Some(id) { None } else { Some(id) });
+                }
+            }
+            Key::Char('x') => {
+                if let Some(id) = self.selected_task().map(|t| t.id) {
+                    if self.active_task_id == Some(id) {
+                        self.select_task(None);
+                    }
+                    self.undo_task_id = Some(id);
+                    let db = self.db.clone();
+                    self.task_operation(async move { db.complete_task(id).await });
+                    self.message = Some("Task completed. Press u to undo.".to_string());
+                }
+            }
+            Key::Char('u') => {
+                if let Some(id) = self.undo_task_id.take() {
+                    let db = self.db.clone();
+                    self.task_operation(async move { db.reopen_task(id).await });
+                }
+            }
+            Key::Char('D') => {
+                if let Some(id) = self.selected_task().map(|t| t.id) {
+                    if self.active_task_id == Some(id) {
+                        self.select_task(None);
+                    }
+                    let db = self.db.clone();
+                    self.task_operation(async move { db.delete_task(id).await });
+                }
+            }
+            Key::Char('a') => self.new_task = Some(String::new()),
+            _ => {}
+        }
+        true
+    }
+
+    fn handle_input_key(&mut self, key: Key) {
+        let Some(text) = self.new_task.as_mut() else {
+            return;
+        };
+        match key {
+            Key::Char(c) => text.push(c),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Esc => self.new_task = None,
+            Key::Enter => {
+                let text = self.new_task.take().unwrap_or_default();
+                let text = text.trim().to_string();
+                if !text.is_empty() {
+                    let db = self.db.clone();
+                    self.task_operation(async move { db.add_task(&text).await });
+                    self.selection = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn next_profile(&mut self) {
+        if self.timer.is_started() {
+            self.message = Some("Reset the timer (r) to switch profiles.".to_string());
+            return;
+        }
+        self.selected_profile = (self.selected_profile + 1) % self.profiles.len();
+        let profile = self.profiles[self.selected_profile].clone();
+        if let Err(e) = self.runtime.block_on(self.db.set_selected_profile(&profile.name)) {
+            self.message = Some(format!("Could not save profile: {}", e));
+        }
+        self.timer = Timer::new(profile).with_task(self.active_task_id);
+    }
+
+    fn move_selected(&mut self, by: isize) {
+        let Some(id) = self.selected_task().map(|t| t.id) else {
+            return;
+        };
+        let target = self.selection.saturating_add_signed(by).min(self.tasks.len() - 1);
+        let db = self.db.clone();
+        self.task_operation(async move { db.move_task(id, target).await });
+        // Grouped tasks stop at the edge of their bucket, short of `target`
+        if let Some(index) = self.tasks.iter().position(|t| t.id == id) {
+            self.selection = index;
+        }
+    }
+
+    fn running_focus_secs(&self, now: SystemTime) -> i64 {
+        if self.timer.phase() == Phase::Focus {
+            self.timer.elapsed(now).min(self.timer.total()).as_secs() as i64
+        } else {
+            0
+        }
+    }
+
+    fn frame(&self, now: SystemTime, (cols, rows): (u16, u16)) -> Vec<String> {
+        let cols = cols as usize;
+        let rows = rows as usize;
+        let accent = self.accent();
+        let mut lines = Vec::new();
+
+        // Header
+        let profile = self.timer.profile();
+        let profile_label = format!("{} {}", profile.name, profile.label());
+        lines.push(
+            Line::default()
+                .push(BOLD, " POMIMI")
+                .pad_to(cols.saturating_sub(profile_label.chars().count() + 1))
+                .push(DIM, &profile_label)
+                .finish(),
+        );
+        lines.push(String::new());
+
+        // Timer
+        let remaining = self.timer.remaining(now).as_secs();
+        let clock = format!("{:02}:{:02}", remaining / 60, remaining % 60);
+        if cols >= 40 && rows >= 20 {
+            for row in big_text(&clock) {
+                lines.push(Line::centered(&row, cols).push(BOLD, &row).finish());
+            }
+        } else {
+            lines.push(Line::centered(&clock, cols).push(BOLD, &clock).finish());
+        }
+        lines.push(String::new());
+
+        let state = if self.timer.is_paused() {
+            "PAUSED"
+        } else if self.timer.is_running() {
+            "RUNNING"
+        } else {
+            "READY"
+        };
+        let per_set = profile.cycles_before_long_break.max(1);
+        let status = format!(
+            "{} · {}/{} · {}",
+            self.timer.phase().label().to_uppercase(),
+            self.timer.cycles_completed() % per_set + 1,
+            per_set,
+            state
+        );
+        lines.push(Line::centered(&status, cols).push(DIM, &status).finish());
+
+        let bar_width = cols.saturating_sub(4).min(40);
+        let progress = self.timer.elapsed(now).as_secs_f32() / self.timer.total().as_secs_f32().max(1.0);
+        let filled = ((bar_width as f32 * progress).round() as usize).min(bar_width);
+        lines.push(
+            Line::centered(&" ".repeat(bar_width), cols)
+                .push(&accent, &"█".repeat(filled))
+                .push(DIM, &"░".repeat(bar_width - filled))
+                .finish(),
+        );
+        lines.push(String::new());
+
+        // Tasks
+        let count = format!("{} OPEN", self.tasks.len());
+        lines.push(
+            Line::default()
+                .push(&format!("{}{}", DIM, BOLD), " PRIORITY TASKS")
+                .pad_to(cols.saturating_sub(count.chars().count() + 1))
+                .push(DIM, &count)
+                .finish(),
+        );
+        lines.push(Line::default().push(DIM, &format!(" {}", "─".repeat(cols.saturating_sub(2)))).finish());
+
+        let footer_rows = 4;
+        let list_rows = rows.saturating_sub(lines.len() + footer_rows).max(1);
+        if self.tasks.is_empty() {
+            lines.push(Line::default().push(DIM, "   No active tasks. Press a to add one.").finish());
+        } else {
+            // Scroll so the selection stays visible
+            let first = self.selection.saturating_sub(list_rows - 1);
+            for (i, task) in self.tasks.iter().enumerate().skip(first).take(list_rows) {
+                let selected = i == self.selection;
+                let active = self.active_task_id == Some(task.id);
+                let tag = if active { "Active Task" } else { "" };
+                let text_width = cols.saturating_sub(8 + tag.chars().count());
+                let mut line = Line::default()
+                    .plain(if selected { " > " } else { "   " })
+                    .push(if active { accent.as_str() } else { DIM }, if active { "■ " } else { "□ " });
+                line = if selected {
+                    line.push(BOLD, &fit(&task.text, text_width))
+                } else {
+                    line.plain(&fit(&task.text, text_width))
+                };
+                lines.push(line.pad_to(cols.saturating_sub(tag.chars().count() + 1)).push(&accent, tag).finish());
+            }
+        }
+
+        // Footer, pinned to the bottom rows
+        while lines.len() + footer_rows < rows {
+            lines.push(String::new());
+        }
+        lines.push(Line::default().push(DIM, &format!(" {}", "─".repeat(cols.saturating_sub(2)))).finish());
+
+        let focus = self.today_secs + self.running_focus_secs(now);
+        let total = format!(" {:02}:{:02} Total Focus Time Today", focus / 3600, (focus % 3600) / 60);
+        lines.push(Line::default().plain(&total).finish());
+
+        lines.push(match (&self.new_task, &self.message) {
+            (Some(text), _) => Line::default().push(&accent, " New task: ").plain(&fit(text, cols.saturating_sub(13))).plain("█").finish(),
+            (None, Some(message)) => Line::default().push(DIM, &fit(&format!(" {}", message), cols)).finish(),
+            (None, None) => String::new(),
+        });
+        let help = if self.new_task.is_some() {
+            " enter add · esc cancel"
+        } else {
+            " space start/pause · s skip · r reset · p profile · j/k move · J/K reorder · enter focus · x done · a add · D delete · q quit"
+        };
+        lines.push(Line::default().push(DIM, &fit(help, cols)).finish());
+
+        lines.truncate(rows);
+        lines
+    }
+}
+
+/// A line built from styled pieces, keeping track of its visible width.
+#[derive(Default)]
+struct Line {
+    text: String,
+    width: usize,
+}
+
+impl Line {
+    /// An empty line indented so `content` ends up centred.
+    fn centered(content: &str, cols: usize) -> Self {
+        Line::default().pad_to(cols.saturating_sub(content.chars().count()) / 2)
+    }
+
+    fn push(mut self, style: &str, text: &str) -> Self {
+        if text.is_empty() {
+            return self;
+        }
+        if style.is_empty() {
+            self.text.push_str(text);
+        } else {
+            self.text.push_str(style);
+            self.text.push_str(text);
+            self.text.push_str(RESET);
+        }
+        self.width += text.chars().count();
+        self
+    }
+
+    fn plain(self, text: &str) -> Self {
+        self.push("", text)
+    }
+
+    fn pad_to(self, column: usize) -> Self {
+        let padding = column.saturating_sub(self.width);
+        self.plain(&" ".repeat(padding))
+    }
+
+    fn finish(self) -> String {
+        self.text
+    }
+}
+
+/// Cuts `text` to `width` characters, marking the cut with an ellipsis.
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    if width > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+/// Five rows of block digits for a clock such as "25:00".
+fn big_text(clock: &str) -> Vec<String> {
+    const DIGITS: [[&str; 5]; 10] = [
+        ["███", "█ █", "█ █", "█ █", "███"],
+        ["  █", "  █", "  █", "  █", "  █"],
+        ["███", "  █", "███", "█  ", "███"],
+        ["███", "  █", "███", "  █", "███"],
+        ["█ █", "█ █", "███", "  █", "  █"],
+        ["███", "█  ", "███", "  █", "███"],
+        ["███", "█  ", "███", "█ █", "███"],
+        ["███", "  █", "  █", "  █", "  █"],
+        ["███", "█ █", "███", "█ █", "███"],
+        ["███", "█ █", "███", "  █", "███"],
+    ];
+    const COLON: [&str; 5] = [" ", "█", " ", "█", " "];
+
+    (0..5)
+        .map(|row| {
+            let glyphs: Vec<String> = clock
+                .chars()
+                .map(|c| {
+                    let glyph = match c.to_digit(10) {
+                        Some(digit) => DIGITS[digit as usize][row],
+                        None => COLON[row],
+                    };
+                    // Doubled horizontally so digits look square in a terminal cell
+                    glyph.chars().flat_map(|c| [c, c]).collect()
+                })
+                .collect();
+            glyphs.join("  ")
+        })
+        .collect()
+}
+
+/// The alternate screen and what is currently drawn on it.
+struct Screen {
+    lines: Vec<String>,
+    size: (u16, u16),
+}
+
+impl Screen {
+    fn enter() -> Self {
+        print!("\x1b[?1049h\x1b[?25l");
+        let mut screen = Screen { lines: Vec::new(), size: (80, 24) };
+        screen.resize();
+        screen
+    }
+
+    /// Picks up the new terminal size and forces a full repaint.
+    fn resize(&mut self) {
+        self.size = terminal::size().unwrap_or((80, 24));
+        self.lines.clear();
+        print!("\x1b[2J");
+    }
+
+    fn draw(&mut self, frame: Vec<String>) {
+        let output = repaint(&self.lines, &frame);
+        if !output.is_empty() {
+            print!("{}", output);
+            let _ = io::stdout().flush();
+        }
+        self.lines = frame;
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Escape sequences that turn the `old` frame into `new`, rewriting only the
+/// rows that differ.
+fn repaint(old: &[String], new: &[String]) -> String {
+    let mut output = String::new();
+    for row in 0..old.len().max(new.len()) {
+        let line = new.get(row);
+        if old.get(row) == line {
+            continue;
+        }
+        output.push_str(&format!("\x1b[{};1H{}\x1b[K", row + 1, line.map_or("", String::as_str)));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn repaints_only_changed_rows() {
+        let old = lines(&["POMIMI", "25:00", "tasks"]);
+        assert_eq!(repaint(&old, &old), "");
+        assert_eq!(repaint(&old, &lines(&["POMIMI", "24:59", "tasks"])), "\x1b[2;1H24:59\x1b[K");
+    }
+
+    #[test]
+    fn clears_rows_a_shorter_frame_no_longer_uses() {
+        let old = lines(&["a", "b", "c"]);
+        assert_eq!(repaint(&old, &lines(&["a"])), "\x1b[2;1H\x1b[K\x1b[3;1H\x1b[K");
+        assert_eq!(repaint(&[], &lines(&["a"])), "\x1b[1;1Ha\x1b[K");
+    }
+
+    #[test]
+    fn big_clock_is_five_even_rows() {
+        let rows = big_text("25:00");
+        assert_eq!(rows.len(), 5);
+        assert!(rows.iter().all(|row| row.chars().count() == 4 * 6 + 2 + 4 * 2));
+        assert_eq!(rows[0], "██████  ██████      ██████  ██████");
+    }
+
+    #[test]
+    fn lines_track_visible_width() {
+        let line = Line::default().push(BOLD, "ab").pad_to(5).plain("c");
+        assert_eq!(line.width, 6);
+        assert_eq!(line.finish(), "\x1b[1mab\x1b[0m   c");
+        assert_eq!(fit("focus on this", 6), "focus…");
+        assert_eq!(fit("short", 6), "short");
+    }
+}